@@ -0,0 +1,652 @@
+use crate::client::{
+    http_client, http_error, json, send, AddressActivity, ApiResult, Client, Direction,
+    TransactionStatus, TransactionSummary, Utxo,
+};
+use crate::endpoint::Endpoint;
+use async_trait::async_trait;
+use bitcoin::hex::DisplayHex;
+use bitcoin::Network;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use types::amount::Amount;
+use types::error::WalletError;
+use types::shared::{Address, Blockchain, Net};
+use types::units::U256;
+
+/// The minimum relay fee rate, used when the node has no fee estimates yet, e.g. on regtest.
+const MIN_RELAY_FEE_RATE: f64 = 1.0;
+
+/// Client for an Esplora-compatible REST API (blockstream.info, mempool.space, electrs).
+pub(super) struct Bitcoin {
+    network: Network,
+    url: String,
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl Client for Bitcoin {
+    fn new(net: Net, endpoint: Option<&Endpoint>, timeout: Duration) -> ApiResult<Self> {
+        let default = Endpoint::new(match net {
+            Net::Main => "https://blockstream.info/api",
+            Net::Test => "https://blockstream.info/testnet/api",
+            Net::Dev | Net::Local => "http://localhost:3002",
+        });
+        Self::with_endpoint(net, endpoint.unwrap_or(&default), timeout)
+    }
+
+    async fn get_balance(&self, address: &Address) -> ApiResult<Amount> {
+        Ok(self.get_address_activity(address).await?.balance)
+    }
+
+    async fn get_address_activity(&self, address: &Address) -> ApiResult<AddressActivity> {
+        let address = self.parse_address(address)?;
+        let stats = json::<AddressStats>(
+            send(self.client.get(format!("{}/address/{}", self.url, address))).await?,
+        )
+        .await?;
+        Ok(AddressActivity {
+            balance: Amount::native(Blockchain::Bitcoin, stats.chain_stats.confirmed_balance()),
+            used: stats.chain_stats.funded_txo_count > 0,
+        })
+    }
+
+    async fn get_utxos(&self, address: &Address) -> ApiResult<Vec<Utxo>> {
+        let address = self.parse_address(address)?;
+        let utxos = json::<Vec<EsploraUtxo>>(
+            send(
+                self.client
+                    .get(format!("{}/address/{}/utxo", self.url, address)),
+            )
+            .await?,
+        )
+        .await?;
+        Ok(utxos
+            .into_iter()
+            .map(|utxo| Utxo {
+                txid: utxo.txid,
+                vout: utxo.vout,
+                value: utxo.value,
+                confirmed: utxo.status.confirmed,
+            })
+            .collect())
+    }
+
+    async fn get_fee_rate(&self, target_blocks: u16) -> ApiResult<f64> {
+        let estimates = json::<HashMap<String, f64>>(
+            send(self.client.get(format!("{}/fee-estimates", self.url))).await?,
+        )
+        .await?;
+        // Estimates are given for a few targets only, so use the nearest faster one.
+        let fee_rate = estimates
+            .iter()
+            .filter_map(|(target, fee_rate)| Some((target.parse::<u16>().ok()?, *fee_rate)))
+            .filter(|&(target, _)| target <= target_blocks)
+            .max_by_key(|&(target, _)| target)
+            .map(|(_, fee_rate)| fee_rate)
+            .unwrap_or(MIN_RELAY_FEE_RATE);
+        Ok(fee_rate.max(MIN_RELAY_FEE_RATE))
+    }
+
+    /// Esplora returns the unconfirmed transactions and a first page of confirmed ones, then
+    /// pages of confirmed transactions after a given one, so pages are fetched until `limit`
+    /// is reached.
+    async fn get_transactions(
+        &self,
+        address: &Address,
+        limit: usize,
+        before: Option<&str>,
+    ) -> ApiResult<Vec<TransactionSummary>> {
+        let address = self.parse_address(address)?.to_string();
+        let mut transactions = vec![];
+        let mut last_seen = before.map(str::to_string);
+        while transactions.len() < limit {
+            let url = match &last_seen {
+                None => format!("{}/address/{address}/txs", self.url),
+                Some(txid) => format!("{}/address/{address}/txs/chain/{txid}", self.url),
+            };
+            let page = json::<Vec<EsploraTransaction>>(send(self.client.get(url)).await?).await?;
+            let next = page
+                .iter()
+                .rev()
+                .find(|transaction| transaction.status.confirmed)
+                .map(|transaction| transaction.txid.clone());
+            transactions.extend(page.iter().map(|transaction| transaction.summary(&address)));
+            match next {
+                Some(txid) if last_seen.as_ref() != Some(&txid) => last_seen = Some(txid),
+                _ => break,
+            }
+        }
+        transactions.truncate(limit);
+        Ok(transactions)
+    }
+
+    async fn broadcast(&self, transaction: &[u8]) -> ApiResult<String> {
+        let response = send(
+            self.client
+                .post(format!("{}/tx", self.url))
+                .body(transaction.to_lower_hex_string()),
+        )
+        .await
+        .map_err(|e| match e {
+            // Esplora answers 400 with the reason the node refused the transaction.
+            WalletError::Rpc { message, .. } => WalletError::Transaction(message),
+            e => e,
+        })?;
+        Ok(response
+            .text()
+            .await
+            .map_err(http_error)?
+            .trim()
+            .to_string())
+    }
+}
+
+impl Bitcoin {
+    pub(crate) fn with_endpoint(
+        net: Net,
+        endpoint: &Endpoint,
+        timeout: Duration,
+    ) -> ApiResult<Self> {
+        Ok(Self {
+            network: Network::from(net),
+            url: endpoint.url.trim_end_matches('/').to_string(),
+            client: http_client(timeout, &endpoint.headers)?,
+        })
+    }
+
+    fn parse_address(&self, address: &Address) -> ApiResult<bitcoin::Address> {
+        bitcoin::Address::from_str(address)
+            .map_err(WalletError::invalid_address)?
+            .require_network(self.network)
+            .map_err(WalletError::invalid_address)
+    }
+}
+
+/// Response of the `GET /address/:address` endpoint.
+#[derive(Deserialize)]
+struct AddressStats {
+    chain_stats: ChainStats,
+}
+
+/// Totals over the confirmed transactions of an address.
+#[derive(Deserialize)]
+struct ChainStats {
+    funded_txo_count: u64,
+    funded_txo_sum: u64,
+    spent_txo_sum: u64,
+}
+
+impl ChainStats {
+    fn confirmed_balance(&self) -> u64 {
+        self.funded_txo_sum.saturating_sub(self.spent_txo_sum)
+    }
+}
+
+/// An element of the `GET /address/:address/utxo` response.
+#[derive(Deserialize)]
+struct EsploraUtxo {
+    txid: String,
+    vout: u32,
+    value: u64,
+    status: TxStatus,
+}
+
+#[derive(Deserialize)]
+struct TxStatus {
+    confirmed: bool,
+    block_time: Option<i64>,
+}
+
+/// An element of the `GET /address/:address/txs` response.
+#[derive(Deserialize)]
+struct EsploraTransaction {
+    txid: String,
+    vin: Vec<Input>,
+    vout: Vec<Output>,
+    fee: u64,
+    status: TxStatus,
+}
+
+#[derive(Deserialize)]
+struct Input {
+    /// The spent output, missing for coinbase inputs.
+    prevout: Option<Output>,
+}
+
+#[derive(Deserialize)]
+struct Output {
+    /// Missing for outputs that don't pay an address, e.g. `OP_RETURN`.
+    scriptpubkey_address: Option<String>,
+    value: u64,
+}
+
+impl EsploraTransaction {
+    /// The transaction as seen from `address`, which pays the fee if it spends an input.
+    fn summary(&self, address: &str) -> TransactionSummary {
+        let spent = self
+            .vin
+            .iter()
+            .filter_map(|input| input.prevout.as_ref())
+            .filter(|output| output.pays(address))
+            .map(|output| U256::from(output.value))
+            .fold(U256::zero(), |sum, value| sum + value);
+        let received = self
+            .vout
+            .iter()
+            .filter(|output| output.pays(address))
+            .map(|output| U256::from(output.value))
+            .fold(U256::zero(), |sum, value| sum + value);
+        let spent = if spent.is_zero() {
+            spent
+        } else {
+            spent.saturating_sub(self.fee.into())
+        };
+        let (direction, amount) = Direction::of(received, spent);
+        let counterparty = match direction {
+            Direction::Incoming => self
+                .vin
+                .iter()
+                .filter_map(|input| input.prevout.as_ref())
+                .find_map(|output| output.other_address(address)),
+            _ => self
+                .vout
+                .iter()
+                .find_map(|output| output.other_address(address)),
+        };
+        TransactionSummary {
+            id: self.txid.clone(),
+            timestamp: self.status.block_time,
+            direction,
+            amount: Amount::native(Blockchain::Bitcoin, amount),
+            fee: Amount::native(Blockchain::Bitcoin, self.fee),
+            status: if self.status.confirmed {
+                TransactionStatus::Confirmed
+            } else {
+                TransactionStatus::Pending
+            },
+            counterparty,
+        }
+    }
+}
+
+impl Output {
+    fn pays(&self, address: &str) -> bool {
+        self.scriptpubkey_address.as_deref() == Some(address)
+    }
+
+    fn other_address(&self, address: &str) -> Option<String> {
+        self.scriptpubkey_address
+            .clone()
+            .filter(|other| other != address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::DEFAULT_TIMEOUT;
+    use serde_json::json;
+
+    const REGTEST_ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    #[tokio::test]
+    async fn get_balance() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", format!("/address/{REGTEST_ADDRESS}").as_str())
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{
+                    "address": "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080",
+                    "chain_stats": {
+                        "funded_txo_count": 2,
+                        "funded_txo_sum": 150000,
+                        "spent_txo_count": 1,
+                        "spent_txo_sum": 50000,
+                        "tx_count": 3
+                    },
+                    "mempool_stats": {
+                        "funded_txo_count": 1,
+                        "funded_txo_sum": 7000,
+                        "spent_txo_count": 0,
+                        "spent_txo_sum": 0,
+                        "tx_count": 1
+                    }
+                }"#,
+            )
+            .create_async()
+            .await;
+
+        let client =
+            Bitcoin::with_endpoint(Net::Local, &Endpoint::new(server.url()), DEFAULT_TIMEOUT)
+                .unwrap();
+        assert_eq!(
+            client
+                .get_balance(&REGTEST_ADDRESS.to_string())
+                .await
+                .unwrap(),
+            Amount::native(Blockchain::Bitcoin, 100000)
+        );
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn get_address_activity() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", format!("/address/{REGTEST_ADDRESS}").as_str())
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{
+                    "chain_stats": {
+                        "funded_txo_count": 1,
+                        "funded_txo_sum": 50000,
+                        "spent_txo_count": 1,
+                        "spent_txo_sum": 50000,
+                        "tx_count": 2
+                    }
+                }"#,
+            )
+            .create_async()
+            .await;
+
+        let client =
+            Bitcoin::with_endpoint(Net::Local, &Endpoint::new(server.url()), DEFAULT_TIMEOUT)
+                .unwrap();
+        // Spent to zero, but used.
+        assert_eq!(
+            client
+                .get_address_activity(&REGTEST_ADDRESS.to_string())
+                .await
+                .unwrap(),
+            AddressActivity {
+                balance: Amount::native(Blockchain::Bitcoin, 0),
+                used: true,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn endpoint_headers() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/fee-estimates")
+            .match_header("x-api-key", "secret")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"6": 8.25}"#)
+            .create_async()
+            .await;
+        let endpoint =
+            Endpoint::new(format!("{}/", server.url())).with_header("x-api-key", "secret");
+        let client = Bitcoin::new(Net::Local, Some(&endpoint), DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(client.get_fee_rate(6).await.unwrap(), 8.25);
+        mock.assert_async().await;
+
+        let endpoint = endpoint.with_header("x-api-key", "line\nbreak");
+        assert!(Bitcoin::new(Net::Local, Some(&endpoint), DEFAULT_TIMEOUT).is_err());
+    }
+
+    #[tokio::test]
+    async fn get_balance_wrong_network() {
+        let server = mockito::Server::new_async().await;
+        let client =
+            Bitcoin::with_endpoint(Net::Main, &Endpoint::new(server.url()), DEFAULT_TIMEOUT)
+                .unwrap();
+        assert!(matches!(
+            client.get_balance(&REGTEST_ADDRESS.to_string()).await,
+            Err(WalletError::InvalidAddress(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_balance_http_error() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", format!("/address/{REGTEST_ADDRESS}").as_str())
+            .with_status(400)
+            .with_body("Invalid Bitcoin address")
+            .create_async()
+            .await;
+
+        let client =
+            Bitcoin::with_endpoint(Net::Dev, &Endpoint::new(server.url()), DEFAULT_TIMEOUT)
+                .unwrap();
+        assert_eq!(
+            client.get_balance(&REGTEST_ADDRESS.to_string()).await,
+            Err(WalletError::Rpc {
+                code: 400,
+                message: "Invalid Bitcoin address".to_string()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn get_utxos() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", format!("/address/{REGTEST_ADDRESS}/utxo").as_str())
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[
+                    {
+                        "txid": "3a2b1f7c5e3b1f0a1c5d6e7f8a9b0c1d2e3f4a5b6c7d8e9f0a1b2c3d4e5f6a7b",
+                        "vout": 1,
+                        "status": {"confirmed": true, "block_height": 101},
+                        "value": 50000
+                    },
+                    {
+                        "txid": "9f8e7d6c5b4a39281706f5e4d3c2b1a09f8e7d6c5b4a39281706f5e4d3c2b1a0",
+                        "vout": 0,
+                        "status": {"confirmed": false},
+                        "value": 1200
+                    }
+                ]"#,
+            )
+            .create_async()
+            .await;
+
+        let client =
+            Bitcoin::with_endpoint(Net::Local, &Endpoint::new(server.url()), DEFAULT_TIMEOUT)
+                .unwrap();
+        let utxos = client
+            .get_utxos(&REGTEST_ADDRESS.to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            utxos[0],
+            Utxo {
+                txid: "3a2b1f7c5e3b1f0a1c5d6e7f8a9b0c1d2e3f4a5b6c7d8e9f0a1b2c3d4e5f6a7b"
+                    .to_string(),
+                vout: 1,
+                value: 50000,
+                confirmed: true,
+            }
+        );
+        assert!(!utxos[1].confirmed);
+    }
+
+    #[tokio::test]
+    async fn get_fee_rate() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/fee-estimates")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"1": 20.5, "2": 15.1, "3": 12.0, "6": 8.25, "144": 1.5}"#)
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client =
+            Bitcoin::with_endpoint(Net::Local, &Endpoint::new(server.url()), DEFAULT_TIMEOUT)
+                .unwrap();
+        assert_eq!(client.get_fee_rate(6).await.unwrap(), 8.25);
+        assert_eq!(client.get_fee_rate(5).await.unwrap(), 12.0);
+        assert_eq!(client.get_fee_rate(1000).await.unwrap(), 1.5);
+        mock.assert_async().await;
+
+        server
+            .mock("GET", "/fee-estimates")
+            .with_header("content-type", "application/json")
+            .with_body("{}")
+            .create_async()
+            .await;
+        assert_eq!(client.get_fee_rate(6).await.unwrap(), MIN_RELAY_FEE_RATE);
+    }
+
+    #[tokio::test]
+    async fn get_transactions() {
+        const OTHER: &str = "bcrt1qm34lsc65zpw79lxes69zkqmk6ee3ewf0j77s3h";
+        let mut server = mockito::Server::new_async().await;
+        // A pending incoming transaction, and a confirmed one that sends 30000 sats and pays
+        // 500 sats of fee.
+        server
+            .mock("GET", format!("/address/{REGTEST_ADDRESS}/txs").as_str())
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!([
+                    {
+                        "txid": "aa",
+                        "vin": [{"prevout": {"scriptpubkey_address": OTHER, "value": 9000}}],
+                        "vout": [
+                            {"scriptpubkey_address": REGTEST_ADDRESS, "value": 7000},
+                            {"scriptpubkey_address": OTHER, "value": 1800}
+                        ],
+                        "fee": 200,
+                        "status": {"confirmed": false}
+                    },
+                    {
+                        "txid": "bb",
+                        "vin": [{"prevout": {"scriptpubkey_address": REGTEST_ADDRESS, "value": 50000}}],
+                        "vout": [
+                            {"scriptpubkey_address": OTHER, "value": 30000},
+                            {"scriptpubkey_address": REGTEST_ADDRESS, "value": 19500}
+                        ],
+                        "fee": 500,
+                        "status": {"confirmed": true, "block_height": 102, "block_time": 1700000600}
+                    }
+                ])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let next_page = server
+            .mock(
+                "GET",
+                format!("/address/{REGTEST_ADDRESS}/txs/chain/bb").as_str(),
+            )
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!([
+                    {
+                        "txid": "cc",
+                        "vin": [{"prevout": null}],
+                        "vout": [{"scriptpubkey_address": REGTEST_ADDRESS, "value": 50000}],
+                        "fee": 0,
+                        "status": {"confirmed": true, "block_height": 101, "block_time": 1700000000}
+                    },
+                    {
+                        "txid": "dd",
+                        "vin": [],
+                        "vout": [],
+                        "fee": 0,
+                        "status": {"confirmed": true}
+                    }
+                ])
+                .to_string(),
+            )
+            .expect(2)
+            .create_async()
+            .await;
+
+        // The end of the history.
+        server
+            .mock(
+                "GET",
+                format!("/address/{REGTEST_ADDRESS}/txs/chain/dd").as_str(),
+            )
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create_async()
+            .await;
+
+        let client =
+            Bitcoin::with_endpoint(Net::Local, &Endpoint::new(server.url()), DEFAULT_TIMEOUT)
+                .unwrap();
+        let address = REGTEST_ADDRESS.to_string();
+        let transactions = client.get_transactions(&address, 3, None).await.unwrap();
+        assert_eq!(
+            transactions
+                .iter()
+                .map(|transaction| transaction.id.as_str())
+                .collect::<Vec<_>>(),
+            ["aa", "bb", "cc"]
+        );
+        assert_eq!(
+            transactions[0],
+            TransactionSummary {
+                id: "aa".to_string(),
+                timestamp: None,
+                direction: Direction::Incoming,
+                amount: Amount::native(Blockchain::Bitcoin, 7000),
+                fee: Amount::native(Blockchain::Bitcoin, 200),
+                status: TransactionStatus::Pending,
+                counterparty: Some(OTHER.to_string()),
+            }
+        );
+        assert_eq!(
+            transactions[1],
+            TransactionSummary {
+                id: "bb".to_string(),
+                timestamp: Some(1_700_000_600),
+                direction: Direction::Outgoing,
+                amount: Amount::native(Blockchain::Bitcoin, 30000),
+                fee: Amount::native(Blockchain::Bitcoin, 500),
+                status: TransactionStatus::Confirmed,
+                counterparty: Some(OTHER.to_string()),
+            }
+        );
+        // A coinbase has no sender.
+        assert_eq!(transactions[2].direction, Direction::Incoming);
+        assert_eq!(transactions[2].counterparty, None);
+
+        let transactions = client
+            .get_transactions(&address, 10, Some("bb"))
+            .await
+            .unwrap();
+        assert_eq!(transactions.len(), 2);
+        // Only as many pages as needed are fetched.
+        next_page.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn broadcast() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/tx")
+            .match_body("0200ff")
+            .with_body("b6f6991d03df0e2e04dafffcd6bc418aac66049e2cd74b80f14ac86db1e3f0da")
+            .create_async()
+            .await;
+
+        let client =
+            Bitcoin::with_endpoint(Net::Local, &Endpoint::new(server.url()), DEFAULT_TIMEOUT)
+                .unwrap();
+        assert_eq!(
+            client.broadcast(&[0x02, 0x00, 0xff]).await.unwrap(),
+            "b6f6991d03df0e2e04dafffcd6bc418aac66049e2cd74b80f14ac86db1e3f0da"
+        );
+        mock.assert_async().await;
+
+        server
+            .mock("POST", "/tx")
+            .with_status(400)
+            .with_body("sendrawtransaction RPC error: bad-txns-inputs-missingorspent")
+            .create_async()
+            .await;
+        let error = client.broadcast(&[0x02]).await.unwrap_err();
+        assert!(matches!(error, WalletError::Transaction(_)));
+        assert!(error.to_string().contains("missingorspent"));
+    }
+}
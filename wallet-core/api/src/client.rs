@@ -1,67 +1,598 @@
+use crate::bitcoin::Bitcoin;
+use crate::endpoint::{ApiConfig, Endpoint};
+use crate::ethereum::Ethereum;
 use crate::solana::Solana;
-use std::error::Error;
-use types::shared::{Address, Blockchain, Net};
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use types::amount::{Amount, Asset};
+use types::error::WalletError;
+use types::shared::{Address, Blockchain, Commitment, Net};
+use types::units::U256;
 
-pub type ApiResult<T> = Result<T, Box<dyn Error>>;
+pub type ApiResult<T> = Result<T, WalletError>;
 
+/// How long a node or API server has to answer a request.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A balance of a fungible token held by an address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TokenBalance {
+    /// The account holding the balance.
+    pub account: Address,
+    /// An amount of an [`Asset::Token`], whose symbol is known when the blockchain stores it
+    /// with the token, e.g. ERC-20.
+    pub amount: Amount,
+}
+
+impl TokenBalance {
+    /// The mint or contract address of the token.
+    pub fn token(&self) -> &str {
+        self.amount.asset.address().map_or("", |token| token)
+    }
+}
+
+/// The confirmed balance of an address, and whether it ever received coins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressActivity {
+    pub balance: Amount,
+    /// Whether a confirmed transaction paid the address, even if its coins were spent since.
+    pub used: bool,
+}
+
+/// An unspent transaction output of an address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Utxo {
+    pub txid: String,
+    pub vout: u32,
+    /// In satoshis.
+    pub value: u64,
+    pub confirmed: bool,
+}
+
+/// Whether a transaction moved funds into or out of an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+    /// The address sent to itself, or only paid a fee.
+    SelfTransfer,
+}
+
+impl Direction {
+    /// The direction and amount of a transaction for an address that `received` and `spent`
+    /// base units of the native coin in it, not counting the fee.
+    pub(crate) fn of(received: U256, spent: U256) -> (Direction, U256) {
+        match received.cmp(&spent) {
+            Ordering::Greater => (Direction::Incoming, received - spent),
+            Ordering::Less => (Direction::Outgoing, spent - received),
+            Ordering::Equal => (Direction::SelfTransfer, U256::zero()),
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Incoming => write!(f, "in"),
+            Direction::Outgoing => write!(f, "out"),
+            Direction::SelfTransfer => write!(f, "self"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    /// Not in a block yet.
+    Pending,
+    Confirmed,
+    /// Included in a block, but reverted.
+    Failed,
+}
+
+impl Display for TransactionStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionStatus::Pending => write!(f, "pending"),
+            TransactionStatus::Confirmed => write!(f, "confirmed"),
+            TransactionStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// A past transaction of an address, as seen from that address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransactionSummary {
+    /// The transaction id, hash or signature, which is also the paging cursor.
+    pub id: String,
+    /// The time of its block, in seconds since the Unix epoch, if it's known.
+    pub timestamp: Option<i64>,
+    pub direction: Direction,
+    /// The native coins received or sent by the address, without the fee.
+    pub amount: Amount,
+    /// The fee of the transaction, paid by the sender.
+    pub fee: Amount,
+    pub status: TransactionStatus,
+    /// The sender of an incoming transaction or the recipient of an outgoing one, the first
+    /// if there are several.
+    pub counterparty: Option<Address>,
+}
+
+/// The clients of all blockchains on a network.
+///
+/// Each blockchain is reached through the public node or API server of the network, unless the
+/// [`ApiConfig`] has another endpoint for it. Each request fails with [`WalletError::Timeout`]
+/// if it isn't answered within the timeout of the config, and [`Api::with_timeout`] also limits
+/// whole operations. Operations are futures, so they can be cancelled by dropping them, e.g.
+/// with `tokio::select!`; a transaction that was already sent may still be confirmed.
 pub struct Api {
-    client: Box<dyn Client>,
+    net: Net,
+    bitcoin: Arc<dyn Client>,
+    ethereum: Arc<dyn Client>,
+    solana: Arc<dyn Client>,
+    /// How long each operation may take, if limited.
+    timeout: Option<Duration>,
 }
 
 impl Api {
-    pub fn new(blockchain: Blockchain, net: Net) -> Self {
-        Self {
-            client: Api::get_client(blockchain, net),
+    /// Fails if a header of an endpoint isn't a valid HTTP header.
+    pub fn new(net: Net, config: &ApiConfig) -> ApiResult<Self> {
+        let endpoint = |blockchain| config.endpoints.get(&blockchain);
+        Ok(Self {
+            net,
+            bitcoin: Arc::new(Bitcoin::new(
+                net,
+                endpoint(Blockchain::Bitcoin),
+                config.timeout,
+            )?),
+            ethereum: Arc::new(Ethereum::new(
+                net,
+                endpoint(Blockchain::Ethereum),
+                config.timeout,
+            )?),
+            solana: Arc::new(Solana::new(
+                net,
+                endpoint(Blockchain::Solana),
+                config.timeout,
+            )?),
+            timeout: None,
+        })
+    }
+
+    /// The same clients, whose operations fail with [`WalletError::Timeout`] if they don't
+    /// complete within `timeout`, however many requests they send. The balances of
+    /// [`Api::get_balances`] are limited one by one.
+    pub fn with_timeout(&self, timeout: Duration) -> Api {
+        Api {
+            net: self.net,
+            bitcoin: self.bitcoin.clone(),
+            ethereum: self.ethereum.clone(),
+            solana: self.solana.clone(),
+            timeout: Some(timeout),
         }
     }
 
-    fn get_client(blockchain: Blockchain, net: Net) -> Box<dyn Client> {
+    pub fn net(&self) -> Net {
+        self.net
+    }
+
+    /// Runs `operation` within the timeout of the operations, if any.
+    async fn timed<T>(&self, operation: impl Future<Output = ApiResult<T>>) -> ApiResult<T> {
+        let Some(timeout) = self.timeout else {
+            return operation.await;
+        };
+        tokio::time::timeout(timeout, operation)
+            .await
+            .map_err(|_| {
+                WalletError::Timeout(format!(
+                    "The operation didn't complete within {} ms",
+                    timeout.as_millis()
+                ))
+            })?
+    }
+
+    fn client(&self, blockchain: Blockchain) -> &dyn Client {
         match blockchain {
-            Blockchain::Solana => Box::new(Solana::new(net)),
-            Blockchain::Bitcoin => Box::new(Solana::new(net)),
-            Blockchain::Ethereum => Box::new(Solana::new(net)),
+            Blockchain::Bitcoin => self.bitcoin.as_ref(),
+            Blockchain::Ethereum => self.ethereum.as_ref(),
+            Blockchain::Solana => self.solana.as_ref(),
         }
     }
 
-    pub fn get_balance(&self, address: &Address) -> ApiResult<u64> {
-        self.client.get_balance(address)
+    pub async fn get_balance(
+        &self,
+        blockchain: Blockchain,
+        address: &Address,
+    ) -> ApiResult<Amount> {
+        self.timed(self.client(blockchain).get_balance(address))
+            .await
+    }
+
+    /// Queries the balances of addresses on any blockchains concurrently. The results are in
+    /// the order of `addresses`, and one failed query doesn't fail the others.
+    pub async fn get_balances(
+        &self,
+        addresses: &[(Blockchain, Address)],
+    ) -> Vec<ApiResult<Amount>> {
+        join_all(
+            addresses
+                .iter()
+                .map(|(blockchain, address)| self.get_balance(*blockchain, address)),
+        )
+        .await
+    }
+
+    /// Queries the balances of addresses of a blockchain concurrently, with whether they were
+    /// ever used, as [`Api::get_balances`] does.
+    pub async fn get_address_activities(
+        &self,
+        blockchain: Blockchain,
+        addresses: &[Address],
+    ) -> Vec<ApiResult<AddressActivity>> {
+        let client = self.client(blockchain);
+        join_all(
+            addresses
+                .iter()
+                .map(|address| self.timed(client.get_address_activity(address))),
+        )
+        .await
+    }
+
+    /// Sends `amount` of a native coin or a token from the key `secret_key` to the owner `to`,
+    /// on the blockchain of the asset. Waits until the transaction reaches `commitment`, and
+    /// returns its signature.
+    pub async fn transfer(
+        &self,
+        secret_key: &[u8],
+        to: &Address,
+        amount: &Amount,
+        commitment: Commitment,
+    ) -> ApiResult<String> {
+        let client = self.client(amount.asset.blockchain());
+        self.timed(async {
+            match &amount.asset {
+                Asset::Native { .. } => client.transfer(secret_key, to, amount, commitment).await,
+                Asset::Token { address, .. } => {
+                    client
+                        .transfer_token(secret_key, address, to, amount, commitment)
+                        .await
+                }
+            }
+        })
+        .await
+    }
+
+    /// The balances of `owner` in `tokens`, which are empty on blockchains without tokens.
+    ///
+    /// Solana lists the token accounts of an owner, so all its balances are returned whatever
+    /// `tokens` holds. Ethereum can't, so only the ERC-20 contracts in `tokens` are queried.
+    pub async fn get_token_balances(
+        &self,
+        blockchain: Blockchain,
+        owner: &Address,
+        tokens: &[Address],
+    ) -> ApiResult<Vec<TokenBalance>> {
+        self.timed(self.client(blockchain).get_token_balances(owner, tokens))
+            .await
+    }
+
+    pub async fn get_utxos(
+        &self,
+        blockchain: Blockchain,
+        address: &Address,
+    ) -> ApiResult<Vec<Utxo>> {
+        self.timed(self.client(blockchain).get_utxos(address)).await
+    }
+
+    /// The fee rate, in sat/vB, for a transaction to confirm within `target_blocks` blocks.
+    pub async fn get_fee_rate(&self, blockchain: Blockchain, target_blocks: u16) -> ApiResult<f64> {
+        self.timed(self.client(blockchain).get_fee_rate(target_blocks))
+            .await
+    }
+
+    /// The transactions of `address`, newest first: at most `limit` of them, older than the
+    /// transaction `before` if given. The id of the last one is the cursor of the next page.
+    pub async fn get_transactions(
+        &self,
+        blockchain: Blockchain,
+        address: &Address,
+        limit: usize,
+        before: Option<&str>,
+    ) -> ApiResult<Vec<TransactionSummary>> {
+        self.timed(
+            self.client(blockchain)
+                .get_transactions(address, limit, before),
+        )
+        .await
+    }
+
+    /// Broadcasts a signed, serialized transaction and returns its id.
+    pub async fn broadcast(&self, blockchain: Blockchain, transaction: &[u8]) -> ApiResult<String> {
+        self.timed(self.client(blockchain).broadcast(transaction))
+            .await
+    }
+
+    /// Requests `amount` of test coins for `to` and waits until they are confirmed.
+    pub async fn request_airdrop(&self, to: &Address, amount: &Amount) -> ApiResult<String> {
+        self.timed(
+            self.client(amount.asset.blockchain())
+                .request_airdrop(to, amount),
+        )
+        .await
     }
 }
 
-pub(crate) trait Client {
-    fn new(net: Net) -> Self
+#[async_trait]
+pub(crate) trait Client: Send + Sync {
+    /// Connects to `endpoint`, or to the public node or API server of `net` if there's none.
+    fn new(net: Net, endpoint: Option<&Endpoint>, timeout: Duration) -> ApiResult<Self>
     where
         Self: Sized;
 
-    fn get_balance(&self, address: &Address) -> ApiResult<u64>;
+    async fn get_balance(&self, address: &Address) -> ApiResult<Amount>;
+
+    /// Sends an amount of the native coin.
+    async fn transfer(
+        &self,
+        _secret_key: &[u8],
+        _to: &Address,
+        _amount: &Amount,
+        _commitment: Commitment,
+    ) -> ApiResult<String> {
+        Err(unsupported(
+            "Transfers are not supported on this blockchain yet",
+        ))
+    }
+
+    async fn request_airdrop(&self, _to: &Address, _amount: &Amount) -> ApiResult<String> {
+        Err(unsupported("Airdrops are not supported on this blockchain"))
+    }
+
+    async fn get_token_balances(
+        &self,
+        _owner: &Address,
+        _tokens: &[Address],
+    ) -> ApiResult<Vec<TokenBalance>> {
+        Ok(vec![])
+    }
+
+    /// Sends an amount of `token`, whose decimals are those of `amount`.
+    async fn transfer_token(
+        &self,
+        _secret_key: &[u8],
+        _token: &Address,
+        _to: &Address,
+        _amount: &Amount,
+        _commitment: Commitment,
+    ) -> ApiResult<String> {
+        Err(unsupported("Tokens are not supported on this blockchain"))
+    }
+
+    /// The balance of an address and whether it was ever used, on blockchains whose wallets
+    /// derive a new address per payment.
+    async fn get_address_activity(&self, _address: &Address) -> ApiResult<AddressActivity> {
+        Err(unsupported(
+            "Address activity is not supported on this blockchain",
+        ))
+    }
+
+    async fn get_utxos(&self, _address: &Address) -> ApiResult<Vec<Utxo>> {
+        Err(unsupported(
+            "This blockchain has no unspent transaction outputs",
+        ))
+    }
+
+    async fn get_fee_rate(&self, _target_blocks: u16) -> ApiResult<f64> {
+        Err(unsupported(
+            "Fee rates are not supported on this blockchain",
+        ))
+    }
+
+    async fn get_transactions(
+        &self,
+        _address: &Address,
+        _limit: usize,
+        _before: Option<&str>,
+    ) -> ApiResult<Vec<TransactionSummary>> {
+        Err(unsupported(
+            "Transaction history is not supported on this blockchain",
+        ))
+    }
+
+    async fn broadcast(&self, _transaction: &[u8]) -> ApiResult<String> {
+        Err(unsupported(
+            "Broadcasting raw transactions is not supported on this blockchain",
+        ))
+    }
+}
+
+fn unsupported(message: &str) -> WalletError {
+    WalletError::UnsupportedBlockchain(message.to_string())
+}
+
+/// An HTTP client that sends `headers` with every request, and whose requests fail after
+/// `timeout`.
+pub(crate) fn http_client(
+    timeout: Duration,
+    headers: &BTreeMap<String, String>,
+) -> ApiResult<reqwest::Client> {
+    let headers = headers
+        .iter()
+        .map(|(name, value)| {
+            let header = HeaderName::try_from(name)
+                .ok()
+                .zip(HeaderValue::try_from(value).ok());
+            header.ok_or_else(|| WalletError::Network(format!("Invalid HTTP header {name}")))
+        })
+        .collect::<ApiResult<HeaderMap>>()?;
+    Ok(reqwest::Client::builder()
+        .timeout(timeout)
+        .default_headers(headers)
+        .build()
+        // Only fails if TLS can't be initialized, which rustls always can.
+        .expect("Failed to build the HTTP client"))
+}
+
+/// Sends an HTTP request. A response with an error status is returned as an RPC error with
+/// the status as code and the body as message.
+pub(crate) async fn send(request: RequestBuilder) -> ApiResult<Response> {
+    let response = request.send().await.map_err(http_error)?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(WalletError::Rpc {
+            code: status.as_u16() as i64,
+            message: response.text().await.unwrap_or_default(),
+        });
+    }
+    Ok(response)
+}
+
+pub(crate) async fn json<T: DeserializeOwned>(response: Response) -> ApiResult<T> {
+    response.json().await.map_err(http_error)
+}
+
+pub(crate) fn http_error(error: reqwest::Error) -> WalletError {
+    if error.is_timeout() {
+        WalletError::Timeout(error.to_string())
+    } else {
+        WalletError::network(error)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use types::Net;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use types::shared::Net;
 
-    struct MockClient;
+    /// Answers after `delay`, and counts the requests being answered at the same time.
+    struct MockClient {
+        delay: Duration,
+        pending: Arc<AtomicUsize>,
+        max_pending: Arc<AtomicUsize>,
+    }
 
+    impl MockClient {
+        fn with_delay(delay: Duration) -> Self {
+            MockClient {
+                delay,
+                pending: Arc::default(),
+                max_pending: Arc::default(),
+            }
+        }
+    }
+
+    #[async_trait]
     impl Client for MockClient {
-        fn new(_: Net) -> Self
+        fn new(_: Net, _: Option<&Endpoint>, _: Duration) -> ApiResult<Self>
         where
             Self: Sized,
         {
-            MockClient
+            Ok(MockClient::with_delay(Duration::ZERO))
         }
 
-        fn get_balance(&self, _: &Address) -> ApiResult<u64> {
-            Ok(12)
+        async fn get_balance(&self, address: &Address) -> ApiResult<Amount> {
+            let pending = self.pending.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_pending.fetch_max(pending, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            let units = address
+                .parse::<u64>()
+                .map_err(|_| WalletError::invalid_address(address))?;
+            Ok(Amount::native(Blockchain::Solana, units))
         }
     }
 
-    #[test]
-    fn get_balance() {
-        let address: Address = "u62NqVqrWp2wE47R8STvCFGwN7XxCq6YcWuYcAwsAWo".to_string();
+    #[tokio::test]
+    async fn get_balance() {
+        let address: Address = "12".to_string();
         assert_eq!(
-            MockClient::new(Net::Test).get_balance(&address).unwrap(),
-            12
+            MockClient::new(Net::Test, None, DEFAULT_TIMEOUT)
+                .unwrap()
+                .get_balance(&address)
+                .await
+                .unwrap(),
+            Amount::native(Blockchain::Solana, 12)
         );
     }
+
+    #[tokio::test]
+    async fn get_balances_concurrently() {
+        let mock = || MockClient::with_delay(Duration::from_millis(100));
+        let (bitcoin, ethereum, solana) = (mock(), mock(), mock());
+        let max_pending = [&bitcoin, &ethereum, &solana].map(|c| c.max_pending.clone());
+        let api = Api {
+            net: Net::Test,
+            bitcoin: Arc::new(bitcoin),
+            ethereum: Arc::new(ethereum),
+            solana: Arc::new(solana),
+            timeout: None,
+        };
+        let balances = api
+            .get_balances(&[
+                (Blockchain::Bitcoin, "1".to_string()),
+                (Blockchain::Ethereum, "2".to_string()),
+                (Blockchain::Solana, "3".to_string()),
+                (Blockchain::Solana, "x".to_string()),
+            ])
+            .await;
+        assert_eq!(
+            balances[..3]
+                .iter()
+                .map(|balance| balance.as_ref().unwrap().units.as_u64())
+                .collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert!(matches!(balances[3], Err(WalletError::InvalidAddress(_))));
+        // Both Solana queries were running at the same time.
+        assert_eq!(max_pending[2].load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn operation_timeout() {
+        let mock = || Arc::new(MockClient::with_delay(Duration::from_millis(200)));
+        let api = Api {
+            net: Net::Test,
+            bitcoin: mock(),
+            ethereum: mock(),
+            solana: mock(),
+            timeout: None,
+        };
+        let address: Address = "7".to_string();
+        let error = api
+            .with_timeout(Duration::from_millis(50))
+            .get_balance(Blockchain::Bitcoin, &address)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WalletError::Timeout(_)));
+        let balances = api
+            .with_timeout(Duration::from_secs(5))
+            .get_balances(&[(Blockchain::Bitcoin, address.clone())])
+            .await;
+        assert_eq!(balances[0], Ok(Amount::native(Blockchain::Solana, 7)));
+        // The operations of the original are still unlimited.
+        assert!(api.get_balance(Blockchain::Bitcoin, &address).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        // Accepts connections but never answers.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let client = http_client(Duration::from_millis(100), &BTreeMap::new()).unwrap();
+        let error = send(client.get(url)).await.unwrap_err();
+        assert!(matches!(error, WalletError::Timeout(_)));
+    }
 }
@@ -0,0 +1,56 @@
+use crate::client::DEFAULT_TIMEOUT;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use types::shared::Blockchain;
+
+/// A node or API server that a client sends its requests to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Endpoint {
+    pub url: String,
+    /// Sent with every request, e.g. the API key of a private RPC provider.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// The Etherscan-compatible API of a block explorer that lists the Ethereum transactions
+    /// of an address, e.g. `https://eth.blockscout.com/api`. It gets the same headers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explorer: Option<String>,
+}
+
+impl Endpoint {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: BTreeMap::new(),
+            explorer: None,
+        }
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_explorer(mut self, url: impl Into<String>) -> Self {
+        self.explorer = Some(url.into());
+        self
+    }
+}
+
+/// How an [`Api`](crate::client::Api) reaches the blockchains of a network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiConfig {
+    /// Used instead of the public node or API server of a blockchain on the network.
+    pub endpoints: HashMap<Blockchain, Endpoint>,
+    /// How long a node or API server has to answer a request.
+    pub timeout: Duration,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: HashMap::new(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
@@ -0,0 +1,1519 @@
+use crate::client::{
+    http_client, json, send, ApiResult, Client, Direction, TokenBalance, TransactionStatus,
+    TransactionSummary,
+};
+use crate::endpoint::Endpoint;
+use async_trait::async_trait;
+use bitcoin::hex::{DisplayHex, FromHex};
+use bitcoin::secp256k1::{Message, Secp256k1, SecretKey};
+use futures::future::try_join_all;
+use futures::join;
+use keypair::to_checksum_address;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+use types::amount::{Amount, Asset};
+use types::error::WalletError;
+use types::shared::{Address, Blockchain, Commitment, Net};
+use types::units::U256;
+
+/// Gas used by a transfer of ether to an account without code.
+const TRANSFER_GAS: u64 = 21_000;
+/// Margin over the estimated gas of a contract call, in case the state changes before it's mined.
+const GAS_ESTIMATE_MARGIN_PERCENT: u64 = 20;
+/// EIP-2718 type of EIP-1559 transactions.
+const EIP1559_TRANSACTION_TYPE: u8 = 0x02;
+
+/// Transactions of the same address in the block of the paging cursor that are fetched in
+/// addition to a page, since the cursor is one of them.
+const SAME_BLOCK_MARGIN: usize = 16;
+
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Long enough for a block to be finalized on mainnet, about two epochs.
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(20 * 60);
+
+/// Client for a standard Ethereum JSON-RPC endpoint (geth, publicnode, anvil, hardhat).
+///
+/// Nodes don't index transactions by address, so the history is read from the
+/// Etherscan-compatible API of the block explorer of the endpoint, if it has one.
+pub(super) struct Ethereum {
+    url: String,
+    explorer: Option<String>,
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl Client for Ethereum {
+    /// The public nodes come with the Blockscout explorer of their network, while another
+    /// endpoint only has the explorer configured with it.
+    fn new(net: Net, endpoint: Option<&Endpoint>, timeout: Duration) -> ApiResult<Self> {
+        let default = match net {
+            Net::Main => Endpoint::new("https://ethereum-rpc.publicnode.com")
+                .with_explorer("https://eth.blockscout.com/api"),
+            Net::Test => Endpoint::new("https://ethereum-sepolia-rpc.publicnode.com")
+                .with_explorer("https://eth-sepolia.blockscout.com/api"),
+            Net::Dev => Endpoint::new("https://ethereum-holesky-rpc.publicnode.com")
+                .with_explorer("https://eth-holesky.blockscout.com/api"),
+            Net::Local => Endpoint::new("http://localhost:8545"),
+        };
+        Self::with_endpoint(endpoint.unwrap_or(&default), timeout)
+    }
+
+    async fn get_balance(&self, address: &Address) -> ApiResult<Amount> {
+        parse_address(address)?;
+        let balance = self
+            .call::<String>("eth_getBalance", json!([address, "latest"]))
+            .await?;
+        Ok(Amount::native(
+            Blockchain::Ethereum,
+            parse_quantity(&balance)?,
+        ))
+    }
+
+    async fn transfer(
+        &self,
+        secret_key: &[u8],
+        to: &Address,
+        amount: &Amount,
+        commitment: Commitment,
+    ) -> ApiResult<String> {
+        let secret_key = SigningKey::parse(secret_key)?;
+        let to = parse_address(to)?;
+        self.send(
+            &secret_key,
+            to,
+            amount.units,
+            vec![],
+            TRANSFER_GAS.into(),
+            commitment,
+        )
+        .await
+    }
+
+    async fn get_token_balances(
+        &self,
+        owner: &Address,
+        tokens: &[Address],
+    ) -> ApiResult<Vec<TokenBalance>> {
+        let holder = parse_address(owner)?;
+        try_join_all(tokens.iter().map(|token| async move {
+            let balance_of = [&selector("balanceOf(address)")[..], &abi_address(&holder)].concat();
+            let (decimals, symbol) = (selector("decimals()"), selector("symbol()"));
+            let (balance, decimals, symbol) = join!(
+                self.eth_call(token, &balance_of),
+                self.eth_call(token, &decimals),
+                self.eth_call(token, &symbol),
+            );
+            let (balance, decimals) = (decode_uint(&balance?)?, decode_uint(&decimals?)?);
+            let decimals = u8::try_from(decimals)
+                .map_err(|_| WalletError::Network(format!("{token} has {decimals} decimals")))?;
+            let asset = Asset::Token {
+                blockchain: Blockchain::Ethereum,
+                address: token.clone(),
+                // `symbol` is optional in ERC-20.
+                symbol: symbol.ok().and_then(|result| decode_string(&result)),
+            };
+            Ok(TokenBalance {
+                account: owner.clone(),
+                amount: Amount::new(asset, balance, decimals),
+            })
+        }))
+        .await
+    }
+
+    async fn transfer_token(
+        &self,
+        secret_key: &[u8],
+        token: &Address,
+        to: &Address,
+        amount: &Amount,
+        commitment: Commitment,
+    ) -> ApiResult<String> {
+        let secret_key = SigningKey::parse(secret_key)?;
+        let data = [
+            &selector("transfer(address,uint256)")[..],
+            &abi_address(&parse_address(to)?),
+            &abi_uint(amount.units),
+        ]
+        .concat();
+        let from = address_of(&secret_key);
+        let balance_of = [
+            &selector("balanceOf(address)")[..],
+            &abi_address(&parse_address(&from)?),
+        ]
+        .concat();
+        let balance = decode_uint(&self.eth_call(token, &balance_of).await?)?;
+        if balance < amount.units {
+            return Err(WalletError::insufficient_funds(
+                amount.clone(),
+                Amount::new(amount.asset.clone(), balance, amount.decimals),
+            ));
+        }
+        let call = json!({ "from": from, "to": token, "data": to_hex(&data) });
+        // Some tokens return false instead of reverting when they refuse a transfer, so it is
+        // simulated first. Tokens that return nothing, like USDT, only fail by reverting.
+        let result = decode_data(
+            &self
+                .call::<String>("eth_call", json!([call, "latest"]))
+                .await?,
+        )?;
+        if !result.is_empty() && decode_uint(&result)?.is_zero() {
+            return Err(WalletError::Transaction(format!(
+                "{token} refused the transfer: transfer returned false"
+            )));
+        }
+        let gas = parse_quantity(
+            &self
+                .call::<String>("eth_estimateGas", json!([call]))
+                .await?,
+        )?;
+        self.send(
+            &secret_key,
+            parse_address(token)?,
+            U256::zero(),
+            data,
+            gas * (100 + GAS_ESTIMATE_MARGIN_PERCENT) / 100,
+            commitment,
+        )
+        .await
+    }
+
+    /// Lists the transactions sent by or to the address with the `txlist` action of the block
+    /// explorer. Internal transfers of contracts and token transfers are not listed.
+    async fn get_transactions(
+        &self,
+        address: &Address,
+        limit: usize,
+        before: Option<&str>,
+    ) -> ApiResult<Vec<TransactionSummary>> {
+        parse_address(address)?;
+        let explorer = self.explorer.as_ref().ok_or_else(|| {
+            WalletError::UnsupportedBlockchain(
+                "No block explorer is known for the transaction history of this network"
+                    .to_string(),
+            )
+        })?;
+        let mut query = vec![
+            ("module", "account".to_string()),
+            ("action", "txlist".to_string()),
+            ("address", address.clone()),
+            ("sort", "desc".to_string()),
+        ];
+        // Transactions are listed by block, so the pages of the cursor end with its block,
+        // whose transactions up to the cursor are skipped. They are fetched until the cursor
+        // and `limit` transactions after it are found, or the history ends.
+        if let Some(hash) = before {
+            let block = self
+                .call::<Option<TransactionByHash>>("eth_getTransactionByHash", json!([hash]))
+                .await?
+                .and_then(|transaction| transaction.block_number)
+                .ok_or_else(|| WalletError::Network(format!("{hash} is not in a block")))?;
+            query.push(("endblock", parse_quantity(&block)?.to_string()));
+        }
+        let size = limit + before.map_or(0, |_| SAME_BLOCK_MARGIN);
+        query.push(("offset", size.to_string()));
+        let mut transactions = vec![];
+        let mut start = if before.is_some() { None } else { Some(0) };
+        for page in 1.. {
+            let mut query = query.clone();
+            query.push(("page", page.to_string()));
+            let response =
+                json::<ExplorerResponse>(send(self.client.get(explorer).query(&query)).await?)
+                    .await?;
+            // An empty history is reported like an error, with an empty list as result.
+            let mut fetched = serde_json::from_value::<Vec<ExplorerTransaction>>(response.result)
+                .map_err(|_| {
+                WalletError::Network(format!("Block explorer: {}", response.message))
+            })?;
+            let count = fetched.len();
+            transactions.append(&mut fetched);
+            if let Some(hash) = before.filter(|_| start.is_none()) {
+                start = transactions
+                    .iter()
+                    .position(|transaction| transaction.hash.eq_ignore_ascii_case(hash))
+                    .map(|i| i + 1);
+            }
+            let found = start.is_some_and(|start| transactions.len() >= start + limit);
+            if found || count < size {
+                break;
+            }
+        }
+        let start = start.ok_or_else(|| {
+            WalletError::InvalidInput(format!(
+                "Transaction {} is not in the history of {address}",
+                before.unwrap_or_default()
+            ))
+        })?;
+        transactions
+            .iter()
+            .skip(start)
+            .take(limit)
+            .map(|transaction| transaction.summary(address))
+            .collect()
+    }
+}
+
+impl Ethereum {
+    pub(crate) fn with_endpoint(endpoint: &Endpoint, timeout: Duration) -> ApiResult<Self> {
+        Ok(Self {
+            url: endpoint.url.trim_end_matches('/').to_string(),
+            explorer: endpoint.explorer.clone(),
+            client: http_client(timeout, &endpoint.headers)?,
+        })
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> ApiResult<T> {
+        let response = json::<RpcResponse<T>>(
+            send(
+                self.client
+                    .post(&self.url)
+                    .json(&json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params})),
+            )
+            .await?,
+        )
+        .await?;
+        match response {
+            RpcResponse {
+                error: Some(error), ..
+            } => Err(WalletError::Rpc {
+                code: error.code,
+                message: format!("{method} failed: {}", error.message),
+            }),
+            RpcResponse {
+                result: Some(result),
+                ..
+            } => Ok(result),
+            _ => Err(WalletError::Network(format!("{method} returned no result"))),
+        }
+    }
+
+    /// Signs a transaction from `secret_key` to `to`, sends it, and waits until it reaches
+    /// `commitment`.
+    async fn send(
+        &self,
+        secret_key: &SecretKey,
+        to: [u8; 20],
+        value: U256,
+        data: Vec<u8>,
+        gas_limit: U256,
+        commitment: Commitment,
+    ) -> ApiResult<String> {
+        let from = address_of(secret_key);
+        let (chain_id, nonce, fees, balance) = join!(
+            self.call::<String>("eth_chainId", json!([])),
+            self.call::<String>("eth_getTransactionCount", json!([from, "pending"])),
+            self.fees(),
+            self.call::<String>("eth_getBalance", json!([from, "pending"])),
+        );
+        let (chain_id, nonce) = (parse_quantity(&chain_id?)?, parse_quantity(&nonce?)?);
+        let (max_priority_fee_per_gas, max_fee_per_gas) = fees?;
+        // The node would refuse a transaction whose value and maximum fee exceed the balance.
+        let (balance, cost) = (
+            parse_quantity(&balance?)?,
+            value.saturating_add(gas_limit.saturating_mul(max_fee_per_gas)),
+        );
+        if balance < cost {
+            return Err(WalletError::insufficient_funds(
+                Amount::native(Blockchain::Ethereum, cost),
+                Amount::native(Blockchain::Ethereum, balance),
+            ));
+        }
+        let transaction = Eip1559Transaction {
+            chain_id,
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit,
+            to,
+            value,
+            data,
+        };
+        let raw = transaction.sign(secret_key);
+        let hash = self
+            .call::<String>("eth_sendRawTransaction", json!([to_hex(&raw)]))
+            .await?;
+        self.confirm(&hash, commitment).await?;
+        Ok(hash)
+    }
+
+    /// Runs a read-only contract call against the latest block and returns its raw result.
+    async fn eth_call(&self, contract: &Address, data: &[u8]) -> ApiResult<Vec<u8>> {
+        parse_address(contract)?;
+        let result = self
+            .call::<String>(
+                "eth_call",
+                json!([{"to": contract, "data": to_hex(data)}, "latest"]),
+            )
+            .await?;
+        decode_data(&result)
+    }
+
+    /// The priority fee and maximum fee per gas: the median priority fee of the last blocks,
+    /// and room for the base fee to double.
+    async fn fees(&self) -> ApiResult<(U256, U256)> {
+        let history = self
+            .call::<FeeHistory>("eth_feeHistory", json!(["0x5", "latest", [50]]))
+            .await?;
+        // The last base fee is the one of the next block.
+        let base_fee = history
+            .base_fee_per_gas
+            .last()
+            .map(|fee| parse_quantity(fee))
+            .transpose()?
+            .ok_or_else(|| WalletError::network("eth_feeHistory returned no base fee"))?;
+        let mut rewards = history
+            .reward
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|reward| reward.first())
+            .map(|reward| parse_quantity(reward))
+            .collect::<ApiResult<Vec<_>>>()?;
+        rewards.sort();
+        let priority_fee = rewards.get(rewards.len() / 2).copied().unwrap_or_default();
+        Ok((priority_fee, base_fee * 2 + priority_fee))
+    }
+
+    /// Waits until the transaction is included in a block, and for `Commitment::Finalized`,
+    /// until that block is finalized.
+    async fn confirm(&self, hash: &str, commitment: Commitment) -> ApiResult<()> {
+        let start = Instant::now();
+        let mut block = None;
+        loop {
+            if block.is_none() {
+                block = self
+                    .call::<Option<Receipt>>("eth_getTransactionReceipt", json!([hash]))
+                    .await?
+                    .map(|receipt| match receipt.status.as_str() {
+                        "0x1" => parse_quantity(&receipt.block_number),
+                        _ => Err(WalletError::Transaction(format!("{hash} reverted"))),
+                    })
+                    .transpose()?;
+            }
+            match block {
+                Some(_) if commitment != Commitment::Finalized => return Ok(()),
+                Some(block) => {
+                    let finalized = self
+                        .call::<Option<Block>>("eth_getBlockByNumber", json!(["finalized", false]))
+                        .await?
+                        .map(|finalized| parse_quantity(&finalized.number))
+                        .transpose()?;
+                    if finalized.is_some_and(|finalized| finalized >= block) {
+                        return Ok(());
+                    }
+                }
+                None => {}
+            }
+            if start.elapsed() > CONFIRMATION_TIMEOUT {
+                return Err(WalletError::Transaction(format!(
+                    "{hash} was not {commitment} in time"
+                )));
+            }
+            tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeeHistory {
+    base_fee_per_gas: Vec<String>,
+    reward: Option<Vec<Vec<String>>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Receipt {
+    block_number: String,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct Block {
+    number: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionByHash {
+    /// Missing while the transaction is pending.
+    block_number: Option<String>,
+}
+
+/// Response of the Etherscan-compatible API of a block explorer, whose `result` is an error
+/// message if the request failed.
+#[derive(Deserialize)]
+struct ExplorerResponse {
+    message: String,
+    result: Value,
+}
+
+/// An element of the `txlist` result, whose numbers are decimal strings.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExplorerTransaction {
+    hash: String,
+    #[serde(rename = "timeStamp")]
+    timestamp: String,
+    from: String,
+    /// Empty for contract creations.
+    to: String,
+    value: String,
+    gas_used: String,
+    gas_price: String,
+    is_error: String,
+}
+
+impl ExplorerTransaction {
+    /// The transaction as seen from `address`. The value of a failed transaction isn't moved.
+    fn summary(&self, address: &str) -> ApiResult<TransactionSummary> {
+        let number = |value: &str| {
+            U256::from_dec_str(value)
+                .map_err(|_| WalletError::Network(format!("Invalid number in {}", self.hash)))
+        };
+        let failed = self.is_error == "1";
+        let value = if failed {
+            U256::zero()
+        } else {
+            number(&self.value)?
+        };
+        let (from, to) = (
+            self.from.eq_ignore_ascii_case(address),
+            self.to.eq_ignore_ascii_case(address),
+        );
+        let received = if to { value } else { U256::zero() };
+        let spent = if from { value } else { U256::zero() };
+        let (direction, amount) = Direction::of(received, spent);
+        let counterparty = if from { &self.to } else { &self.from };
+        Ok(TransactionSummary {
+            id: self.hash.clone(),
+            timestamp: self.timestamp.parse().ok(),
+            direction,
+            amount: Amount::native(Blockchain::Ethereum, amount),
+            fee: Amount::native(
+                Blockchain::Ethereum,
+                number(&self.gas_used)?.saturating_mul(number(&self.gas_price)?),
+            ),
+            status: if failed {
+                TransactionStatus::Failed
+            } else {
+                TransactionStatus::Confirmed
+            },
+            counterparty: Some(counterparty.clone())
+                .filter(|other| !other.is_empty() && !other.eq_ignore_ascii_case(address)),
+        })
+    }
+}
+
+/// The secret key of a transfer, erased on drop like the keypairs it comes from.
+struct SigningKey(SecretKey);
+
+impl SigningKey {
+    fn parse(secret_key: &[u8]) -> ApiResult<Self> {
+        SecretKey::from_slice(secret_key)
+            .map(SigningKey)
+            .map_err(WalletError::invalid_key)
+    }
+}
+
+impl Deref for SigningKey {
+    type Target = SecretKey;
+
+    fn deref(&self) -> &SecretKey {
+        &self.0
+    }
+}
+
+impl Drop for SigningKey {
+    fn drop(&mut self) {
+        self.0.non_secure_erase();
+    }
+}
+
+/// An EIP-1559 (type 2) transaction without access list.
+struct Eip1559Transaction {
+    chain_id: U256,
+    nonce: U256,
+    max_priority_fee_per_gas: U256,
+    max_fee_per_gas: U256,
+    gas_limit: U256,
+    to: [u8; 20],
+    value: U256,
+    data: Vec<u8>,
+}
+
+impl Eip1559Transaction {
+    fn fields(&self) -> Vec<Rlp> {
+        vec![
+            Rlp::uint(self.chain_id),
+            Rlp::uint(self.nonce),
+            Rlp::uint(self.max_priority_fee_per_gas),
+            Rlp::uint(self.max_fee_per_gas),
+            Rlp::uint(self.gas_limit),
+            Rlp::Bytes(self.to.to_vec()),
+            Rlp::uint(self.value),
+            Rlp::Bytes(self.data.clone()),
+            Rlp::List(vec![]),
+        ]
+    }
+
+    /// The hash signed by the sender: `keccak256(0x02 || rlp(fields))`.
+    fn signing_hash(&self) -> [u8; 32] {
+        let mut payload = vec![EIP1559_TRANSACTION_TYPE];
+        payload.extend(Rlp::List(self.fields()).encode());
+        Keccak256::digest(payload).into()
+    }
+
+    /// The signed transaction, `0x02 || rlp(fields, y_parity, r, s)`, as sent to the network.
+    fn sign(&self, secret_key: &SecretKey) -> Vec<u8> {
+        let message = Message::from_digest(self.signing_hash());
+        let (recovery_id, signature) = Secp256k1::new()
+            .sign_ecdsa_recoverable(&message, secret_key)
+            .serialize_compact();
+        let mut fields = self.fields();
+        fields.push(Rlp::uint(recovery_id.to_i32() as u64));
+        fields.push(Rlp::Bytes(strip_leading_zeros(&signature[..32]).to_vec()));
+        fields.push(Rlp::Bytes(strip_leading_zeros(&signature[32..]).to_vec()));
+        let mut raw = vec![EIP1559_TRANSACTION_TYPE];
+        raw.extend(Rlp::List(fields).encode());
+        raw
+    }
+}
+
+/// A Recursive Length Prefix item, the serialization used by Ethereum transactions.
+enum Rlp {
+    Bytes(Vec<u8>),
+    List(Vec<Rlp>),
+}
+
+impl Rlp {
+    /// An integer is encoded as its big-endian bytes without leading zeros, so 0 is empty.
+    fn uint(value: impl Into<U256>) -> Self {
+        Rlp::Bytes(strip_leading_zeros(&value.into().to_big_endian()).to_vec())
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Rlp::Bytes(bytes) if bytes.len() == 1 && bytes[0] < 0x80 => bytes.clone(),
+            Rlp::Bytes(bytes) => [Rlp::header(0x80, bytes.len()), bytes.clone()].concat(),
+            Rlp::List(items) => {
+                let payload = items.iter().flat_map(Rlp::encode).collect::<Vec<_>>();
+                [Rlp::header(0xc0, payload.len()), payload].concat()
+            }
+        }
+    }
+
+    fn header(offset: u8, len: usize) -> Vec<u8> {
+        if len < 56 {
+            return vec![offset + len as u8];
+        }
+        let len = strip_leading_zeros(&len.to_be_bytes()).to_vec();
+        [vec![offset + 55 + len.len() as u8], len].concat()
+    }
+}
+
+/// The first 4 bytes of the Keccak-256 hash of a function signature, which select the function
+/// of a contract call, e.g. `transfer(address,uint256)`.
+fn selector(signature: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// An address as a 32-byte ABI word, left-padded with zeros.
+fn abi_address(address: &[u8; 20]) -> [u8; 32] {
+    let mut word = [0; 32];
+    word[12..].copy_from_slice(address);
+    word
+}
+
+fn abi_uint(value: impl Into<U256>) -> [u8; 32] {
+    value.into().to_big_endian()
+}
+
+/// Decodes the `0x` hex data returned by `eth_call`.
+fn decode_data(result: &str) -> ApiResult<Vec<u8>> {
+    result
+        .strip_prefix("0x")
+        .and_then(|hex| Vec::from_hex(hex).ok())
+        .ok_or_else(|| WalletError::Network(format!("Invalid eth_call result: {result}")))
+}
+
+/// Decodes a `uint256` returned by a contract call.
+fn decode_uint(result: &[u8]) -> ApiResult<U256> {
+    let word = result.get(..32).ok_or_else(|| {
+        WalletError::invalid_address(
+            "Contract call returned no value, is the address an ERC-20 contract?",
+        )
+    })?;
+    Ok(U256::from_big_endian(word))
+}
+
+/// Decodes a `string` returned by a contract call. Some early tokens, like MKR, return a
+/// `bytes32` padded with zeros instead.
+fn decode_string(result: &[u8]) -> Option<String> {
+    let bytes = if result.len() == 32 {
+        let end = result.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+        &result[..end]
+    } else {
+        let offset = usize::try_from(decode_uint(result).ok()?).ok()?;
+        let len = usize::try_from(decode_uint(result.get(offset..)?).ok()?).ok()?;
+        result.get(offset + 32..)?.get(..len)?
+    };
+    String::from_utf8(bytes.to_vec()).ok()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", bytes.to_lower_hex_string())
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// Parses a JSON-RPC hex quantity, e.g. `0x1bc16d674ec80000`.
+fn parse_quantity(quantity: &str) -> ApiResult<U256> {
+    quantity
+        .strip_prefix("0x")
+        .filter(|digits| !digits.is_empty())
+        .and_then(|digits| U256::from_str_radix(digits, 16).ok())
+        .ok_or_else(|| WalletError::Network(format!("Invalid quantity: {quantity}")))
+}
+
+/// Parses a `0x` address. A mixed-case address must match its EIP-55 checksum, so that a typo in
+/// it isn't sent funds; all lowercase or uppercase addresses have no checksum.
+fn parse_address(address: &str) -> ApiResult<[u8; 20]> {
+    let bytes = address
+        .strip_prefix("0x")
+        .and_then(|hex| <[u8; 20]>::from_hex(hex).ok())
+        .ok_or_else(|| {
+            WalletError::InvalidAddress(format!("Invalid Ethereum address: {address}"))
+        })?;
+    let hex = &address[2..];
+    let mixed_case =
+        hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    if mixed_case && to_checksum_address(&bytes) != address {
+        return Err(WalletError::InvalidAddress(format!(
+            "Invalid EIP-55 checksum of Ethereum address: {address}"
+        )));
+    }
+    Ok(bytes)
+}
+
+/// The address of a key: the last 20 bytes of the Keccak-256 hash of its public key.
+fn address_of(secret_key: &SecretKey) -> String {
+    let public_key = secret_key.public_key(&Secp256k1::signing_only());
+    let hash = Keccak256::digest(&public_key.serialize_uncompressed()[1..]);
+    format!("0x{}", hash[12..].to_lower_hex_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::DEFAULT_TIMEOUT;
+    use bitcoin::secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+    use mockito::Matcher;
+
+    const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+    /// The first default account of anvil and hardhat.
+    const SECRET_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const ADDRESS: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+    const TO: &str = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+
+    fn secret_key() -> Vec<u8> {
+        Vec::from_hex(SECRET_KEY).unwrap()
+    }
+
+    fn eth(wei: u64) -> Amount {
+        Amount::native(Blockchain::Ethereum, wei)
+    }
+
+    fn usdc() -> Asset {
+        Asset::Token {
+            blockchain: Blockchain::Ethereum,
+            address: USDC.to_string(),
+            symbol: Some("USDC".to_string()),
+        }
+    }
+
+    async fn mock_rpc(server: &mut mockito::Server, method: &str, result: Value) -> mockito::Mock {
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": method })))
+            .with_header("content-type", "application/json")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string())
+            .create_async()
+            .await
+    }
+
+    #[test]
+    fn rlp() {
+        assert_eq!(Rlp::Bytes(b"dog".to_vec()).encode(), b"\x83dog");
+        assert_eq!(
+            Rlp::List(vec![
+                Rlp::Bytes(b"cat".to_vec()),
+                Rlp::Bytes(b"dog".to_vec())
+            ])
+            .encode(),
+            b"\xc8\x83cat\x83dog"
+        );
+        assert_eq!(Rlp::Bytes(vec![]).encode(), [0x80]);
+        assert_eq!(Rlp::List(vec![]).encode(), [0xc0]);
+        assert_eq!(Rlp::uint(0).encode(), [0x80]);
+        assert_eq!(Rlp::uint(15).encode(), [0x0f]);
+        assert_eq!(Rlp::uint(1024).encode(), [0x82, 0x04, 0x00]);
+        let long = vec![b'a'; 56];
+        assert_eq!(
+            Rlp::Bytes(long.clone()).encode(),
+            [vec![0xb8, 56], long].concat()
+        );
+    }
+
+    #[test]
+    fn address_checksum() {
+        assert!(parse_address(USDC).is_ok());
+        assert!(parse_address(&USDC.to_lowercase()).is_ok());
+        assert!(parse_address(&format!("0x{}", USDC[2..].to_uppercase())).is_ok());
+        // The `b` of `0xA0b8` in uppercase.
+        let typo = USDC.replacen("A0b8", "A0B8", 1);
+        assert!(matches!(
+            parse_address(&typo),
+            Err(WalletError::InvalidAddress(message)) if message.contains("checksum")
+        ));
+        assert!(parse_address("0xA0b86991").is_err());
+    }
+
+    #[test]
+    fn sign_eip1559_transaction() {
+        let secret_key = SecretKey::from_slice(&secret_key()).unwrap();
+        assert_eq!(address_of(&secret_key), ADDRESS);
+        let transaction = Eip1559Transaction {
+            chain_id: 31337.into(),
+            nonce: 0.into(),
+            max_priority_fee_per_gas: 1_000_000_000.into(),
+            max_fee_per_gas: 3_000_000_000_u64.into(),
+            gas_limit: 21_000.into(),
+            to: parse_address(TO).unwrap(),
+            value: U256::exp10(18),
+            data: vec![],
+        };
+        let raw = transaction.sign(&secret_key);
+        assert_eq!(raw[0], EIP1559_TRANSACTION_TYPE);
+        // A list longer than 55 bytes, followed by the chain id, 31337.
+        assert_eq!(raw[1], 0xf8);
+        assert_eq!(raw[2] as usize, raw.len() - 3);
+        assert_eq!(raw[3..6], [0x82, 0x7a, 0x69]);
+
+        // The signature recovers the sender.
+        let signature = &raw[raw.len() - 66..];
+        assert_eq!((signature[0], signature[33]), (0xa0, 0xa0));
+        let (r, s) = (&signature[1..33], &signature[34..]);
+        let y_parity = raw[raw.len() - 67];
+        let signature = RecoverableSignature::from_compact(
+            &[r, s].concat(),
+            RecoveryId::from_i32(if y_parity == 0x80 { 0 } else { y_parity as i32 }).unwrap(),
+        )
+        .unwrap();
+        let public_key = Secp256k1::new()
+            .recover_ecdsa(
+                &Message::from_digest(transaction.signing_hash()),
+                &signature,
+            )
+            .unwrap();
+        assert_eq!(public_key, secret_key.public_key(&Secp256k1::new()));
+    }
+
+    async fn mock_eth_call(
+        server: &mut mockito::Server,
+        data: &str,
+        result: impl AsRef<[u8]>,
+    ) -> mockito::Mock {
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_call",
+                "params": [{"to": USDC, "data": data}]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({"jsonrpc": "2.0", "id": 1, "result": to_hex(result.as_ref())}).to_string(),
+            )
+            .create_async()
+            .await
+    }
+
+    #[test]
+    fn abi() {
+        assert_eq!(selector("balanceOf(address)"), [0x70, 0xa0, 0x82, 0x31]);
+        assert_eq!(
+            selector("transfer(address,uint256)"),
+            [0xa9, 0x05, 0x9c, 0xbb]
+        );
+        assert_eq!(decode_uint(&abi_uint(1_000_000)).unwrap(), 1_000_000.into());
+        assert!(decode_uint(&[]).is_err());
+        assert_eq!(decode_uint(&[0xff; 32]).unwrap(), U256::MAX);
+
+        let string = [
+            abi_uint(32).to_vec(),
+            abi_uint(4).to_vec(),
+            b"USDC".to_vec(),
+            vec![0; 28],
+        ]
+        .concat();
+        assert_eq!(decode_string(&string).unwrap(), "USDC");
+        let bytes32 = [b"MKR".to_vec(), vec![0; 29]].concat();
+        assert_eq!(decode_string(&bytes32).unwrap(), "MKR");
+        assert_eq!(decode_string(&string[..40]), None);
+    }
+
+    #[tokio::test]
+    async fn get_token_balances() {
+        let mut server = mockito::Server::new_async().await;
+        let balance = mock_eth_call(
+            &mut server,
+            "0x70a08231000000000000000000000000f39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+            abi_uint(12_500_000),
+        )
+        .await;
+        mock_eth_call(&mut server, "0x313ce567", abi_uint(6)).await;
+        mock_eth_call(
+            &mut server,
+            "0x95d89b41",
+            [
+                abi_uint(32).to_vec(),
+                abi_uint(4).to_vec(),
+                b"USDC".to_vec(),
+                vec![0; 28],
+            ]
+            .concat(),
+        )
+        .await;
+        let client =
+            Ethereum::with_endpoint(&Endpoint::new(server.url()), DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(
+            client
+                .get_token_balances(&ADDRESS.to_string(), &[USDC.to_string()])
+                .await
+                .unwrap(),
+            vec![TokenBalance {
+                account: ADDRESS.to_string(),
+                amount: Amount::new(usdc(), 12_500_000, 6),
+            }]
+        );
+        balance.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn not_a_token() {
+        let mut server = mockito::Server::new_async().await;
+        mock_rpc(&mut server, "eth_call", json!("0x")).await;
+        let client =
+            Ethereum::with_endpoint(&Endpoint::new(server.url()), DEFAULT_TIMEOUT).unwrap();
+        assert!(client
+            .get_token_balances(&ADDRESS.to_string(), &[TO.to_string()])
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn get_balance() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = mock_rpc(&mut server, "eth_getBalance", json!("0x1bc16d674ec80000")).await;
+        let client =
+            Ethereum::with_endpoint(&Endpoint::new(server.url()), DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(
+            client.get_balance(&ADDRESS.to_string()).await.unwrap(),
+            Amount::native(Blockchain::Ethereum, 2_000_000_000_000_000_000_u64)
+        );
+        mock.assert_async().await;
+        assert!(client.get_balance(&"0x1234".to_string()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn get_balance_above_u64() {
+        let mut server = mockito::Server::new_async().await;
+        mock_rpc(&mut server, "eth_getBalance", json!("0x10000000000000000")).await;
+        let client =
+            Ethereum::with_endpoint(&Endpoint::new(server.url()), DEFAULT_TIMEOUT).unwrap();
+        let balance = client.get_balance(&ADDRESS.to_string()).await.unwrap();
+        assert_eq!(balance.units, U256::from(u64::MAX) + 1);
+        assert_eq!(balance.ui_amount(), "18.446744073709551616");
+    }
+
+    #[tokio::test]
+    async fn rpc_error() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/")
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"nonce too low"}}"#,
+            )
+            .create_async()
+            .await;
+        let client =
+            Ethereum::with_endpoint(&Endpoint::new(server.url()), DEFAULT_TIMEOUT).unwrap();
+        let error = client
+            .transfer(
+                &secret_key(),
+                &TO.to_string(),
+                &eth(1),
+                Commitment::Confirmed,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WalletError::Rpc { code: -32000, .. }));
+        assert!(error.to_string().contains("nonce too low"));
+    }
+
+    #[tokio::test]
+    async fn transfer() {
+        let mut server = mockito::Server::new_async().await;
+        mock_rpc(&mut server, "eth_chainId", json!("0x7a69")).await;
+        let nonce = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_getTransactionCount",
+                "params": [ADDRESS, "pending"]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"jsonrpc":"2.0","id":1,"result":"0x3"}"#)
+            .create_async()
+            .await;
+        mock_rpc(
+            &mut server,
+            "eth_feeHistory",
+            json!({
+                "oldestBlock": "0x10",
+                "baseFeePerGas": ["0x1", "0x2", "0x3", "0x4", "0x5", "0x3b9aca00"],
+                "gasUsedRatio": [0.5, 0.5, 0.5, 0.5, 0.5],
+                "reward": [["0x1"], ["0x5"], ["0x2"], ["0x4"], ["0x3"]]
+            }),
+        )
+        .await;
+        mock_rpc(&mut server, "eth_getBalance", json!("0xde0b6b3a7640000")).await;
+        let hash = "0x2f0bd2ac8d4e8b2e4c3f6b5a1f0e9d8c7b6a5f4e3d2c1b0a9f8e7d6c5b4a3f2e";
+        let send = mock_rpc(&mut server, "eth_sendRawTransaction", json!(hash)).await;
+        mock_rpc(
+            &mut server,
+            "eth_getTransactionReceipt",
+            json!({"blockNumber": "0x11", "status": "0x1", "transactionHash": hash}),
+        )
+        .await;
+
+        let client =
+            Ethereum::with_endpoint(&Endpoint::new(server.url()), DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(
+            client.fees().await.unwrap(),
+            (3.into(), 2_000_000_003_u64.into())
+        );
+        assert_eq!(
+            client
+                .transfer(
+                    &secret_key(),
+                    &TO.to_string(),
+                    &eth(1000),
+                    Commitment::Confirmed
+                )
+                .await
+                .unwrap(),
+            hash
+        );
+        nonce.assert_async().await;
+        send.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn reverted_transfer() {
+        let mut server = mockito::Server::new_async().await;
+        mock_rpc(&mut server, "eth_chainId", json!("0x7a69")).await;
+        mock_rpc(&mut server, "eth_getTransactionCount", json!("0x0")).await;
+        mock_rpc(
+            &mut server,
+            "eth_feeHistory",
+            json!({"oldestBlock": "0x1", "baseFeePerGas": ["0x1", "0x1"], "gasUsedRatio": [0.1]}),
+        )
+        .await;
+        mock_rpc(&mut server, "eth_getBalance", json!("0xde0b6b3a7640000")).await;
+        mock_rpc(&mut server, "eth_sendRawTransaction", json!("0xab")).await;
+        mock_rpc(
+            &mut server,
+            "eth_getTransactionReceipt",
+            json!({"blockNumber": "0x2", "status": "0x0"}),
+        )
+        .await;
+        let client =
+            Ethereum::with_endpoint(&Endpoint::new(server.url()), DEFAULT_TIMEOUT).unwrap();
+        let error = client
+            .transfer(
+                &secret_key(),
+                &TO.to_string(),
+                &eth(1),
+                Commitment::Confirmed,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WalletError::Transaction(_)), "{error}");
+    }
+
+    #[tokio::test]
+    async fn insufficient_funds() {
+        let mut server = mockito::Server::new_async().await;
+        mock_rpc(&mut server, "eth_chainId", json!("0x7a69")).await;
+        mock_rpc(&mut server, "eth_getTransactionCount", json!("0x0")).await;
+        mock_rpc(
+            &mut server,
+            "eth_feeHistory",
+            json!({"oldestBlock": "0x1", "baseFeePerGas": ["0x1", "0x1"], "gasUsedRatio": [0.1]}),
+        )
+        .await;
+        mock_rpc(&mut server, "eth_getBalance", json!("0x5208")).await;
+        let send = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(
+                json!({"method": "eth_sendRawTransaction"}),
+            ))
+            .expect(0)
+            .create_async()
+            .await;
+        let client =
+            Ethereum::with_endpoint(&Endpoint::new(server.url()), DEFAULT_TIMEOUT).unwrap();
+        let error = client
+            .transfer(
+                &secret_key(),
+                &TO.to_string(),
+                &eth(1000),
+                Commitment::Confirmed,
+            )
+            .await
+            .unwrap_err();
+        // 21000 gas at a maximum fee of 2 wei, and the value.
+        assert_eq!(
+            error,
+            WalletError::insufficient_funds(eth(2 * 21_000 + 1000), eth(21_000),)
+        );
+        send.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn transfer_token() {
+        let mut server = mockito::Server::new_async().await;
+        let data = "0xa9059cbb00000000000000000000000070997970c51812dc3a010c7d01b50e0d17dc79c8\
+                    00000000000000000000000000000000000000000000000000000000000003e8";
+        let estimate = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_estimateGas",
+                "params": [{"from": ADDRESS, "to": USDC, "data": data}]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"jsonrpc":"2.0","id":1,"result":"0xc350"}"#)
+            .create_async()
+            .await;
+        let balance_of =
+            "0x70a08231000000000000000000000000f39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+        mock_eth_call(&mut server, balance_of, abi_uint(1000)).await;
+        let simulation = mock_eth_call(&mut server, data, abi_uint(1)).await;
+        mock_rpc(&mut server, "eth_chainId", json!("0x7a69")).await;
+        mock_rpc(&mut server, "eth_getTransactionCount", json!("0x0")).await;
+        mock_rpc(
+            &mut server,
+            "eth_feeHistory",
+            json!({"oldestBlock": "0x1", "baseFeePerGas": ["0x1", "0x1"], "gasUsedRatio": [0.1]}),
+        )
+        .await;
+        mock_rpc(&mut server, "eth_getBalance", json!("0xde0b6b3a7640000")).await;
+        let send = mock_rpc(&mut server, "eth_sendRawTransaction", json!("0xab")).await;
+        mock_rpc(
+            &mut server,
+            "eth_getTransactionReceipt",
+            json!({"blockNumber": "0x2", "status": "0x1"}),
+        )
+        .await;
+        let client =
+            Ethereum::with_endpoint(&Endpoint::new(server.url()), DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(
+            client
+                .transfer_token(
+                    &secret_key(),
+                    &USDC.to_string(),
+                    &TO.to_string(),
+                    &Amount::new(usdc(), 1000, 6),
+                    Commitment::Confirmed
+                )
+                .await
+                .unwrap(),
+            "0xab"
+        );
+        simulation.assert_async().await;
+        estimate.assert_async().await;
+        send.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn insufficient_token_balance() {
+        let mut server = mockito::Server::new_async().await;
+        let balance_of =
+            "0x70a08231000000000000000000000000f39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+        mock_eth_call(&mut server, balance_of, abi_uint(999)).await;
+        let client =
+            Ethereum::with_endpoint(&Endpoint::new(server.url()), DEFAULT_TIMEOUT).unwrap();
+        let error = client
+            .transfer_token(
+                &secret_key(),
+                &USDC.to_string(),
+                &TO.to_string(),
+                &Amount::new(usdc(), 1000, 6),
+                Commitment::Confirmed,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            WalletError::insufficient_funds(
+                Amount::new(usdc(), 1000, 6),
+                Amount::new(usdc(), 999, 6),
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn transfer_token_returning_false() {
+        let mut server = mockito::Server::new_async().await;
+        let data = "0xa9059cbb00000000000000000000000070997970c51812dc3a010c7d01b50e0d17dc79c8\
+                    00000000000000000000000000000000000000000000000000000000000003e8";
+        let balance_of =
+            "0x70a08231000000000000000000000000f39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+        mock_eth_call(&mut server, balance_of, abi_uint(1000)).await;
+        mock_eth_call(&mut server, data, abi_uint(0)).await;
+        let send = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(
+                json!({"method": "eth_sendRawTransaction"}),
+            ))
+            .expect(0)
+            .create_async()
+            .await;
+        let client =
+            Ethereum::with_endpoint(&Endpoint::new(server.url()), DEFAULT_TIMEOUT).unwrap();
+        let error = client
+            .transfer_token(
+                &secret_key(),
+                &USDC.to_string(),
+                &TO.to_string(),
+                &Amount::new(usdc(), 1000, 6),
+                Commitment::Confirmed,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WalletError::Transaction(_)), "{error}");
+        send.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn get_transactions() {
+        let mut server = mockito::Server::new_async().await;
+        let explorer_transaction = |hash: &str, from: &str, to: &str, is_error: &str| {
+            json!({
+                "blockNumber": "17",
+                "timeStamp": "1700000000",
+                "hash": hash,
+                "from": from,
+                "to": to,
+                "value": "1000000000000000000",
+                "gas": "21000",
+                "gasPrice": "2000000000",
+                "gasUsed": "21000",
+                "isError": is_error,
+                "txreceipt_status": "1",
+                "contractAddress": ""
+            })
+        };
+        mock_rpc(
+            &mut server,
+            "eth_getTransactionByHash",
+            json!({"hash": "0x03", "blockNumber": "0x11"}),
+        )
+        .await;
+        let txlist = server
+            .mock("GET", "/api")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("action".to_string(), "txlist".to_string()),
+                Matcher::UrlEncoded("address".to_string(), ADDRESS.to_string()),
+                Matcher::UrlEncoded("endblock".to_string(), "17".to_string()),
+                Matcher::UrlEncoded("offset".to_string(), (2 + SAME_BLOCK_MARGIN).to_string()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "status": "1",
+                    "message": "OK",
+                    "result": [
+                        explorer_transaction("0x04", ADDRESS, TO, "0"),
+                        explorer_transaction("0x03", ADDRESS, TO, "0"),
+                        explorer_transaction("0x02", TO, ADDRESS, "0"),
+                        explorer_transaction("0x01", ADDRESS, TO, "1"),
+                        explorer_transaction("0x00", ADDRESS, TO, "0")
+                    ]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let client = Ethereum::with_endpoint(
+            &Endpoint::new(server.url()).with_explorer(format!("{}/api", server.url())),
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        let transactions = client
+            .get_transactions(&ADDRESS.to_string(), 2, Some("0x03"))
+            .await
+            .unwrap();
+        assert_eq!(
+            transactions,
+            [
+                TransactionSummary {
+                    id: "0x02".to_string(),
+                    timestamp: Some(1_700_000_000),
+                    direction: Direction::Incoming,
+                    amount: eth(1_000_000_000_000_000_000),
+                    fee: eth(42_000_000_000_000),
+                    status: TransactionStatus::Confirmed,
+                    counterparty: Some(TO.to_string()),
+                },
+                TransactionSummary {
+                    id: "0x01".to_string(),
+                    timestamp: Some(1_700_000_000),
+                    direction: Direction::SelfTransfer,
+                    amount: eth(0),
+                    fee: eth(42_000_000_000_000),
+                    status: TransactionStatus::Failed,
+                    counterparty: Some(TO.to_string()),
+                }
+            ]
+        );
+        txlist.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn get_transactions_next_page() {
+        let mut server = mockito::Server::new_async().await;
+        let explorer_transaction = |i: usize| {
+            json!({
+                "blockNumber": "17",
+                "timeStamp": "1700000000",
+                "hash": format!("0x{i:02x}"),
+                "from": ADDRESS,
+                "to": TO,
+                "value": "1",
+                "gas": "21000",
+                "gasPrice": "1",
+                "gasUsed": "21000",
+                "isError": "0",
+                "txreceipt_status": "1",
+                "contractAddress": ""
+            })
+        };
+        mock_rpc(
+            &mut server,
+            "eth_getTransactionByHash",
+            json!({"hash": "0x03", "blockNumber": "0x11"}),
+        )
+        .await;
+        // The block of the cursor has more transactions than a page.
+        let size = 1 + SAME_BLOCK_MARGIN;
+        let block = (0..size + 5)
+            .rev()
+            .map(explorer_transaction)
+            .collect::<Vec<_>>();
+        for (page, transactions) in block.chunks(size).enumerate() {
+            server
+                .mock("GET", "/api")
+                .match_query(Matcher::AllOf(vec![
+                    Matcher::UrlEncoded("page".to_string(), (page + 1).to_string()),
+                    Matcher::UrlEncoded("offset".to_string(), size.to_string()),
+                ]))
+                .with_header("content-type", "application/json")
+                .with_body(
+                    json!({"status": "1", "message": "OK", "result": transactions}).to_string(),
+                )
+                .create_async()
+                .await;
+        }
+
+        let client = Ethereum::with_endpoint(
+            &Endpoint::new(server.url()).with_explorer(format!("{}/api", server.url())),
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        let transactions = client
+            .get_transactions(&ADDRESS.to_string(), 1, Some("0x03"))
+            .await
+            .unwrap();
+        assert_eq!(
+            transactions
+                .iter()
+                .map(|transaction| transaction.id.as_str())
+                .collect::<Vec<_>>(),
+            ["0x02"]
+        );
+        let error = client
+            .get_transactions(&ADDRESS.to_string(), 1, Some("0xff"))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WalletError::InvalidInput(_)), "{error}");
+    }
+
+    #[tokio::test]
+    async fn no_transactions() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/api")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"status":"0","message":"No transactions found","result":[]}"#)
+            .create_async()
+            .await;
+        let client = Ethereum::with_endpoint(
+            &Endpoint::new(server.url()).with_explorer(format!("{}/api", server.url())),
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        assert_eq!(
+            client
+                .get_transactions(&ADDRESS.to_string(), 10, None)
+                .await
+                .unwrap(),
+            []
+        );
+        // Local nodes have no explorer.
+        assert!(matches!(
+            Ethereum::new(Net::Local, None, DEFAULT_TIMEOUT)
+                .unwrap()
+                .get_transactions(&ADDRESS.to_string(), 10, None)
+                .await,
+            Err(WalletError::UnsupportedBlockchain(_))
+        ));
+    }
+
+    #[test]
+    fn explorers() {
+        let public = Ethereum::new(Net::Main, None, DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(
+            public.explorer.as_deref(),
+            Some("https://eth.blockscout.com/api")
+        );
+        // The explorer of the public node doesn't follow another endpoint.
+        let endpoint = Endpoint::new("https://eth-mainnet.example.com/v2");
+        let private = Ethereum::new(Net::Main, Some(&endpoint), DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(private.explorer, None);
+        let endpoint = endpoint.with_explorer("https://explorer.example.com/api");
+        let private = Ethereum::new(Net::Main, Some(&endpoint), DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(
+            private.explorer.as_deref(),
+            Some("https://explorer.example.com/api")
+        );
+    }
+
+    /// Needs `anvil` (or `npx hardhat node`) listening on localhost:8545.
+    #[tokio::test]
+    #[ignore]
+    async fn transfer_on_local_node() {
+        let client = Ethereum::new(Net::Local, None, DEFAULT_TIMEOUT).unwrap();
+        let to = TO.to_lowercase();
+        let before = client.get_balance(&to).await.unwrap();
+        client
+            .transfer(
+                &secret_key(),
+                &to,
+                &eth(1_000_000_000),
+                Commitment::Confirmed,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            client.get_balance(&to).await.unwrap().units,
+            before.units + 1_000_000_000
+        );
+    }
+
+    /// Runtime code of a minimal ERC-20 token, "TST" with 6 decimals, that keeps the balance
+    /// of an address in the storage slot of the same number. Written by hand to avoid
+    /// depending on a Solidity compiler.
+    const TEST_TOKEN_CODE: &str =
+        "60003560e01c806370a0823114610037578063313ce5671461004457806395d89b\
+        411461004f578063a9059cbb14610069575b600080fd5b6004355460005260206000f35b6006600052602060\
+        00f35b602060005260036020526254535460e81b60405260606000f35b335460243580821061003257809103\
+        3355600435805482019055600160005260206000f3";
+
+    /// Needs `anvil` listening on localhost:8545.
+    #[tokio::test]
+    #[ignore]
+    async fn transfer_token_on_local_node() {
+        let client = Ethereum::new(Net::Local, None, DEFAULT_TIMEOUT).unwrap();
+        let token = "0x00000000000000000000000000000000000e2c20".to_string();
+        let to = TO.to_lowercase();
+        client
+            .call::<Value>(
+                "anvil_setCode",
+                json!([token, format!("0x{TEST_TOKEN_CODE}")]),
+            )
+            .await
+            .unwrap();
+        client
+            .call::<Value>(
+                "anvil_setStorageAt",
+                json!([
+                    token,
+                    to_hex(&abi_address(&parse_address(ADDRESS).unwrap())),
+                    to_hex(&abi_uint(1_000_000))
+                ]),
+            )
+            .await
+            .unwrap();
+
+        let asset = Asset::Token {
+            blockchain: Blockchain::Ethereum,
+            address: token.clone(),
+            symbol: None,
+        };
+        client
+            .transfer_token(
+                &secret_key(),
+                &token,
+                &to,
+                &Amount::new(asset, 250_000, 6),
+                Commitment::Confirmed,
+            )
+            .await
+            .unwrap();
+        let balances = client
+            .get_token_balances(&to, std::slice::from_ref(&token))
+            .await
+            .unwrap();
+        assert_eq!(balances[0].amount.units, 250_000.into());
+        assert_eq!(balances[0].amount.decimals, 6);
+        assert_eq!(balances[0].amount.asset.symbol(), Some("TST"));
+        let balances = client
+            .get_token_balances(&ADDRESS.to_string(), &[token])
+            .await
+            .unwrap();
+        assert_eq!(balances[0].amount.units, 750_000.into());
+    }
+}
@@ -1,2 +1,5 @@
+mod bitcoin;
 pub mod client;
+pub mod endpoint;
+mod ethereum;
 mod solana;
@@ -1,28 +1,846 @@
-use crate::client::{ApiResult, Client};
-use solana_client::rpc_client::RpcClient;
+use crate::client::{
+    http_client, ApiResult, Client, Direction, TokenBalance, TransactionStatus, TransactionSummary,
+};
+use crate::endpoint::Endpoint;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use futures::join;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::RpcClientConfig;
+use solana_client::rpc_request::{RpcError, RpcRequest, TokenAccountsFilter};
+use solana_client::rpc_response::{RpcConfirmedTransactionStatusWithSignature, RpcKeyedAccount};
+use solana_rpc_client::http_sender::HttpSender;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{keypair_from_seed, Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_system_interface::instruction as system_instruction;
+use spl_associated_token_account_client::address::get_associated_token_address_with_program_id;
+use spl_associated_token_account_client::instruction::create_associated_token_account_idempotent;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::state::Mint;
 use std::str::FromStr;
-use types::shared::{Address, Net};
+use std::time::Duration;
+use types::amount::{Amount, Asset};
+use types::error::WalletError;
+use types::shared::{Address, Blockchain, Commitment, Net};
+use types::units::U256;
+
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
 pub(super) struct Solana {
+    net: Net,
     client: RpcClient,
 }
 
+#[async_trait]
 impl Client for Solana {
-    fn new(net: Net) -> Self {
-        Solana {
-            client: RpcClient::new(match net {
-                Net::Main => "https://api.mainnet-beta.solana.com".to_string(),
-                Net::Test => "https://api.testnet.solana.com".to_string(),
-                Net::Dev => "https://api.devnet.solana.com".to_string(),
-                Net::Local => "http://localhost:8899".to_string(),
-            }),
+    fn new(net: Net, endpoint: Option<&Endpoint>, timeout: Duration) -> ApiResult<Self> {
+        let default = Endpoint::new(match net {
+            Net::Main => "https://api.mainnet-beta.solana.com",
+            Net::Test => "https://api.testnet.solana.com",
+            Net::Dev => "https://api.devnet.solana.com",
+            Net::Local => "http://localhost:8899",
+        });
+        Self::with_endpoint(net, endpoint.unwrap_or(&default), timeout)
+    }
+
+    async fn get_balance(&self, address: &Address) -> ApiResult<Amount> {
+        let lamports = self
+            .client
+            .get_balance(&parse_pubkey(address)?)
+            .await
+            .map_err(rpc_error)?;
+        Ok(Amount::native(Blockchain::Solana, lamports))
+    }
+
+    async fn transfer(
+        &self,
+        secret_key: &[u8],
+        to: &Address,
+        amount: &Amount,
+        commitment: Commitment,
+    ) -> ApiResult<String> {
+        let from = keypair_from_seed(secret_key).map_err(WalletError::invalid_key)?;
+        let to = parse_pubkey(to)?;
+        let amount = amount.to_u64()?;
+        self.send(
+            &from,
+            &[system_instruction::transfer(&from.pubkey(), &to, amount)],
+            amount,
+            commitment,
+        )
+        .await
+    }
+
+    async fn get_token_balances(
+        &self,
+        owner: &Address,
+        _tokens: &[Address],
+    ) -> ApiResult<Vec<TokenBalance>> {
+        let owner = parse_pubkey(owner)?;
+        let accounts = try_join_all([spl_token::id(), spl_token_2022::id()].map(|program| {
+            self.client
+                .get_token_accounts_by_owner(&owner, TokenAccountsFilter::ProgramId(program))
+        }))
+        .await
+        .map_err(rpc_error)?;
+        accounts.iter().flatten().map(token_balance).collect()
+    }
+
+    async fn transfer_token(
+        &self,
+        secret_key: &[u8],
+        token: &Address,
+        to: &Address,
+        amount: &Amount,
+        commitment: Commitment,
+    ) -> ApiResult<String> {
+        let from = keypair_from_seed(secret_key).map_err(WalletError::invalid_key)?;
+        let to = parse_pubkey(to)?;
+        let mint = parse_pubkey(token)?;
+        let units = amount.to_u64()?;
+        let mint_account = self.client.get_account(&mint).await.map_err(rpc_error)?;
+        let program = mint_account.owner;
+        let decimals = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+            .map_err(|_| WalletError::InvalidAddress(format!("{token} is not a token mint")))?
+            .base
+            .decimals;
+        if decimals != amount.decimals {
+            return Err(WalletError::InvalidAmount(format!(
+                "{token} has {decimals} decimals, not {}",
+                amount.decimals
+            )));
+        }
+
+        let source = get_associated_token_address_with_program_id(&from.pubkey(), &mint, &program);
+        let destination = get_associated_token_address_with_program_id(&to, &mint, &program);
+        let balance = match self
+            .client
+            .get_token_account_balance(&source)
+            .await
+            .map_err(rpc_error)
+        {
+            Ok(balance) => balance
+                .amount
+                .parse::<u64>()
+                .map_err(WalletError::network)?,
+            // "Invalid param: could not find account": the owner never held the token.
+            Err(WalletError::Rpc { code: -32602, .. }) => 0,
+            Err(e) => return Err(e),
+        };
+        if balance < units {
+            return Err(WalletError::insufficient_funds(
+                amount.clone(),
+                Amount::new(amount.asset.clone(), balance, decimals),
+            ));
+        }
+        let mut instructions = vec![];
+        if self
+            .client
+            .get_account_with_commitment(&destination, CommitmentConfig::confirmed())
+            .await
+            .map_err(rpc_error)?
+            .value
+            .is_none()
+        {
+            instructions.push(create_associated_token_account_idempotent(
+                &from.pubkey(),
+                &to,
+                &mint,
+                &program,
+            ));
+        }
+        instructions.push(
+            spl_token_2022::instruction::transfer_checked(
+                &program,
+                &source,
+                &mint,
+                &destination,
+                &from.pubkey(),
+                &[],
+                units,
+                decimals,
+            )
+            .map_err(|e| WalletError::Transaction(e.to_string()))?,
+        );
+        self.send(&from, &instructions, 0, commitment).await
+    }
+
+    /// Lists the signatures of the address with `getSignaturesForAddress`, then reads each
+    /// transaction with `getTransaction`. Only the native SOL moved is summarized.
+    async fn get_transactions(
+        &self,
+        address: &Address,
+        limit: usize,
+        before: Option<&str>,
+    ) -> ApiResult<Vec<TransactionSummary>> {
+        parse_pubkey(address)?;
+        let signatures = self
+            .client
+            .send::<Vec<RpcConfirmedTransactionStatusWithSignature>>(
+                RpcRequest::GetSignaturesForAddress,
+                json!([address, {"limit": limit, "before": before}]),
+            )
+            .await
+            .map_err(rpc_error)?;
+        try_join_all(signatures.iter().map(|status| async move {
+            self.client
+                .send::<Option<RpcTransaction>>(
+                    RpcRequest::GetTransaction,
+                    json!([
+                        status.signature,
+                        {"encoding": "json", "maxSupportedTransactionVersion": 0}
+                    ]),
+                )
+                .await
+                .map_err(rpc_error)?
+                .ok_or_else(|| {
+                    WalletError::Network(format!("Transaction {} not found", status.signature))
+                })?
+                .summary(&status.signature, address)
+        }))
+        .await
+    }
+
+    async fn request_airdrop(&self, to: &Address, amount: &Amount) -> ApiResult<String> {
+        if self.net == Net::Main {
+            return Err(WalletError::WrongNetwork(
+                "Airdrops are only available on test networks, not on Main-net".to_string(),
+            ));
+        }
+        let to = parse_pubkey(to)?;
+        let amount = amount.to_u64()?;
+        let blockhash = self
+            .client
+            .get_latest_blockhash()
+            .await
+            .map_err(rpc_error)?;
+        let signature = self
+            .client
+            .request_airdrop_with_blockhash(&to, amount, &blockhash)
+            .await
+            .map_err(rpc_error)?;
+        self.confirm(&signature, &blockhash, Commitment::Confirmed)
+            .await?;
+        Ok(signature.to_string())
+    }
+}
+
+impl Solana {
+    pub(crate) fn with_endpoint(
+        net: Net,
+        endpoint: &Endpoint,
+        timeout: Duration,
+    ) -> ApiResult<Self> {
+        let sender =
+            HttpSender::new_with_client(&endpoint.url, http_client(timeout, &endpoint.headers)?);
+        Ok(Solana {
+            net,
+            client: RpcClient::new_sender(
+                sender,
+                RpcClientConfig::with_commitment(CommitmentConfig::default()),
+            ),
+        })
+    }
+
+    /// Signs a transaction of `instructions` paid by `payer`, sends it and waits until it
+    /// reaches `commitment`. It fails before sending if `payer` can't pay the fee and the
+    /// `lamports` that the instructions spend.
+    async fn send(
+        &self,
+        payer: &Keypair,
+        instructions: &[Instruction],
+        lamports: u64,
+        commitment: Commitment,
+    ) -> ApiResult<String> {
+        let blockhash = self
+            .client
+            .get_latest_blockhash()
+            .await
+            .map_err(rpc_error)?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            blockhash,
+        );
+        let pubkey = payer.pubkey();
+        let (balance, fee) = join!(
+            self.client.get_balance(&pubkey),
+            self.client.get_fee_for_message(&transaction.message),
+        );
+        let (balance, fee) = (balance.map_err(rpc_error)?, fee.map_err(rpc_error)?);
+        if balance < lamports.saturating_add(fee) {
+            return Err(WalletError::insufficient_funds(
+                Amount::native(Blockchain::Solana, lamports.saturating_add(fee)),
+                Amount::native(Blockchain::Solana, balance),
+            ));
+        }
+        let signature = self
+            .client
+            .send_transaction(&transaction)
+            .await
+            .map_err(rpc_error)?;
+        self.confirm(&signature, &blockhash, commitment).await?;
+        Ok(signature.to_string())
+    }
+
+    /// Waits until the transaction reaches `commitment`, or fails once its blockhash has
+    /// expired, after which it can no longer be processed.
+    async fn confirm(
+        &self,
+        signature: &Signature,
+        blockhash: &solana_sdk::hash::Hash,
+        commitment: Commitment,
+    ) -> ApiResult<()> {
+        let commitment = convert(commitment);
+        loop {
+            match self
+                .client
+                .get_signature_status_with_commitment(signature, commitment)
+                .await
+                .map_err(rpc_error)?
+            {
+                Some(Ok(())) => return Ok(()),
+                Some(Err(e)) => return Err(WalletError::Transaction(format!("{signature}: {e}"))),
+                None if !self
+                    .client
+                    .is_blockhash_valid(blockhash, CommitmentConfig::processed())
+                    .await
+                    .map_err(rpc_error)? =>
+                {
+                    return Err(WalletError::Transaction(format!(
+                        "{signature} was not confirmed before its blockhash expired"
+                    )))
+                }
+                None => tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await,
+            }
         }
     }
+}
+
+/// A transaction returned by `getTransaction` with the `json` encoding.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTransaction {
+    block_time: Option<i64>,
+    meta: Option<RpcTransactionMeta>,
+    transaction: RpcTransactionBody,
+}
+
+#[derive(Deserialize)]
+struct RpcTransactionBody {
+    message: RpcMessage,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcMessage {
+    account_keys: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTransactionMeta {
+    err: Option<Value>,
+    fee: u64,
+    pre_balances: Vec<u64>,
+    post_balances: Vec<u64>,
+    /// The accounts of the address lookup tables of a versioned transaction, which follow
+    /// the account keys of its message.
+    #[serde(default)]
+    loaded_addresses: LoadedAddresses,
+}
+
+#[derive(Default, Deserialize)]
+struct LoadedAddresses {
+    writable: Vec<String>,
+    readonly: Vec<String>,
+}
+
+impl RpcTransaction {
+    /// The transaction as seen from `address`. The fee is paid by the first account.
+    fn summary(&self, signature: &str, address: &str) -> ApiResult<TransactionSummary> {
+        let meta = self.meta.as_ref().ok_or_else(|| {
+            WalletError::Network(format!("Transaction {signature} has no status"))
+        })?;
+        // The change of balance of each account, not counting the fee.
+        let changes = meta
+            .pre_balances
+            .iter()
+            .zip(&meta.post_balances)
+            .enumerate()
+            .map(|(i, (&pre, &post))| {
+                let fee = if i == 0 { meta.fee } else { 0 };
+                (U256::from(post) + fee, U256::from(pre))
+            })
+            .collect::<Vec<_>>();
+        let accounts = self
+            .transaction
+            .message
+            .account_keys
+            .iter()
+            .chain(&meta.loaded_addresses.writable)
+            .chain(&meta.loaded_addresses.readonly)
+            .collect::<Vec<_>>();
+        let position = accounts.iter().position(|account| *account == address);
+        let (direction, amount) = position.and_then(|i| changes.get(i)).map_or(
+            (Direction::SelfTransfer, U256::zero()),
+            |&(received, spent)| Direction::of(received, spent),
+        );
+        let counterparty = accounts
+            .iter()
+            .zip(&changes)
+            .enumerate()
+            .filter(|&(i, _)| Some(i) != position)
+            .find(|(_, (_, &(received, spent)))| match direction {
+                Direction::Incoming => received < spent,
+                Direction::Outgoing => received > spent,
+                Direction::SelfTransfer => false,
+            })
+            .map(|(_, (account, _))| account.to_string());
+        Ok(TransactionSummary {
+            id: signature.to_string(),
+            timestamp: self.block_time,
+            direction,
+            amount: Amount::native(Blockchain::Solana, amount),
+            fee: Amount::native(Blockchain::Solana, meta.fee),
+            status: match meta.err {
+                Some(_) => TransactionStatus::Failed,
+                None => TransactionStatus::Confirmed,
+            },
+            counterparty,
+        })
+    }
+}
+
+/// Reads a token account returned by `getTokenAccountsByOwner` with the `jsonParsed`
+/// encoding.
+fn token_balance(account: &RpcKeyedAccount) -> ApiResult<TokenBalance> {
+    let data = serde_json::to_value(&account.account.data).map_err(WalletError::network)?;
+    let info = &data["parsed"]["info"];
+    let amount = &info["tokenAmount"];
+    let invalid = || WalletError::Network(format!("Invalid token account {}", account.pubkey));
+    Ok(TokenBalance {
+        account: account.pubkey.clone(),
+        amount: Amount::new(
+            Asset::Token {
+                blockchain: Blockchain::Solana,
+                address: info["mint"].as_str().ok_or_else(invalid)?.to_string(),
+                symbol: None,
+            },
+            amount["amount"]
+                .as_str()
+                .and_then(|amount| U256::from_dec_str(amount).ok())
+                .ok_or_else(invalid)?,
+            amount["decimals"]
+                .as_u64()
+                .and_then(|decimals| u8::try_from(decimals).ok())
+                .ok_or_else(invalid)?,
+        ),
+    })
+}
+
+fn parse_pubkey(address: &str) -> ApiResult<Pubkey> {
+    Pubkey::from_str(address).map_err(|e| WalletError::InvalidAddress(format!("{address}: {e}")))
+}
+
+/// Keeps the code of errors returned by the node, and tells failed transactions apart from
+/// unreachable nodes.
+fn rpc_error(error: ClientError) -> WalletError {
+    if let Some(e) = error.get_transaction_error() {
+        return WalletError::Transaction(e.to_string());
+    }
+    match error.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, .. }) => {
+            WalletError::Rpc {
+                code: *code,
+                message: message.clone(),
+            }
+        }
+        ClientErrorKind::TransactionError(e) => WalletError::Transaction(e.to_string()),
+        ClientErrorKind::SigningError(e) => WalletError::crypto(e),
+        ClientErrorKind::Reqwest(e) if e.is_timeout() => WalletError::Timeout(e.to_string()),
+        _ => WalletError::network(error),
+    }
+}
+
+fn convert(commitment: Commitment) -> CommitmentConfig {
+    match commitment {
+        Commitment::Processed => CommitmentConfig::processed(),
+        Commitment::Confirmed => CommitmentConfig::confirmed(),
+        Commitment::Finalized => CommitmentConfig::finalized(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::DEFAULT_TIMEOUT;
+    use solana_sdk::native_token::LAMPORTS_PER_SOL;
+    use solana_sdk::program_pack::Pack;
+
+    fn sol(lamports: u64) -> Amount {
+        Amount::native(Blockchain::Solana, lamports)
+    }
+
+    #[tokio::test]
+    async fn no_airdrop_on_main_net() {
+        let to = Keypair::new().pubkey().to_string();
+        let error = Solana::new(Net::Main, None, DEFAULT_TIMEOUT)
+            .unwrap()
+            .request_airdrop(&to, &sol(LAMPORTS_PER_SOL))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WalletError::WrongNetwork(_)), "{error}");
+        assert!(error.to_string().contains("Main-net"));
+    }
+
+    #[test]
+    fn parses_token_account() {
+        let account: RpcKeyedAccount = serde_json::from_value(json!({
+            "pubkey": "C2gJg6tKpQs41PRS1nC8aw3ZKNZK3HQQZGVrDFDup5nx",
+            "account": {
+                "lamports": 2039280,
+                "owner": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+                "executable": false,
+                "rentEpoch": 18446744073709551615u64,
+                "space": 165,
+                "data": {
+                    "program": "spl-token",
+                    "space": 165,
+                    "parsed": {
+                        "type": "account",
+                        "info": {
+                            "mint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                            "owner": "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk",
+                            "state": "initialized",
+                            "isNative": false,
+                            "tokenAmount": {
+                                "amount": "12500000",
+                                "decimals": 6,
+                                "uiAmount": 12.5,
+                                "uiAmountString": "12.5"
+                            }
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let balance = token_balance(&account).unwrap();
+        assert_eq!(
+            balance,
+            TokenBalance {
+                account: "C2gJg6tKpQs41PRS1nC8aw3ZKNZK3HQQZGVrDFDup5nx".to_string(),
+                amount: Amount::new(
+                    Asset::Token {
+                        blockchain: Blockchain::Solana,
+                        address: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+                        symbol: None,
+                    },
+                    12_500_000,
+                    6
+                ),
+            }
+        );
+        assert_eq!(
+            balance.token(),
+            "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+        );
+        assert_eq!(balance.amount.ui_amount(), "12.5");
+    }
+
+    const PAYER: &str = "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk";
+    const RECIPIENT: &str = "C2gJg6tKpQs41PRS1nC8aw3ZKNZK3HQQZGVrDFDup5nx";
+
+    /// A transfer of 1 SOL from `PAYER` to `RECIPIENT`, as returned by `getTransaction`.
+    fn transfer_transaction() -> Value {
+        json!({
+            "slot": 300,
+            "blockTime": 1700000000,
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [10_000_000_000u64, 0, 1],
+                "postBalances": [8_999_995_000u64, 1_000_000_000, 1],
+                "loadedAddresses": {"writable": [], "readonly": []}
+            },
+            "transaction": {
+                "message": {
+                    "accountKeys": [PAYER, RECIPIENT, "11111111111111111111111111111111"]
+                },
+                "signatures": ["5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW"]
+            },
+            "version": 0
+        })
+    }
+
+    #[test]
+    fn summarizes_transaction() {
+        let transaction: RpcTransaction = serde_json::from_value(transfer_transaction()).unwrap();
+        assert_eq!(
+            transaction.summary("sig", PAYER).unwrap(),
+            TransactionSummary {
+                id: "sig".to_string(),
+                timestamp: Some(1_700_000_000),
+                direction: Direction::Outgoing,
+                amount: sol(LAMPORTS_PER_SOL),
+                fee: sol(5000),
+                status: TransactionStatus::Confirmed,
+                counterparty: Some(RECIPIENT.to_string()),
+            }
+        );
+        let received = transaction.summary("sig", RECIPIENT).unwrap();
+        assert_eq!(received.direction, Direction::Incoming);
+        assert_eq!(received.amount, sol(LAMPORTS_PER_SOL));
+        assert_eq!(received.counterparty.as_deref(), Some(PAYER));
+
+        // A failed transaction only costs the fee.
+        let mut failed = transfer_transaction();
+        failed["meta"]["err"] = json!({"InstructionError": [0, "Custom"]});
+        failed["meta"]["postBalances"] = json!([9_999_995_000u64, 0, 1]);
+        let failed = serde_json::from_value::<RpcTransaction>(failed)
+            .unwrap()
+            .summary("sig", PAYER)
+            .unwrap();
+        assert_eq!(failed.status, TransactionStatus::Failed);
+        assert_eq!(failed.direction, Direction::SelfTransfer);
+        assert_eq!(failed.counterparty, None);
+    }
+
+    #[tokio::test]
+    async fn get_transactions() {
+        let mut server = mockito::Server::new_async().await;
+        let signatures = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "method": "getSignaturesForAddress",
+                "params": [PAYER, {"limit": 1, "before": "older"}]
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({"jsonrpc": "2.0", "id": 1, "result": [{
+                    "signature": "sig",
+                    "slot": 300,
+                    "err": null,
+                    "memo": null,
+                    "blockTime": 1700000000,
+                    "confirmationStatus": "finalized"
+                }]})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::PartialJson(
+                json!({"method": "getTransaction", "params": ["sig"]}),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({"jsonrpc": "2.0", "id": 1, "result": transfer_transaction()}).to_string(),
+            )
+            .create_async()
+            .await;
+        let solana = Solana {
+            net: Net::Local,
+            client: RpcClient::new_with_timeout(server.url(), DEFAULT_TIMEOUT),
+        };
+        let transactions = solana
+            .get_transactions(&PAYER.to_string(), 1, Some("older"))
+            .await
+            .unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].id, "sig");
+        assert_eq!(transactions[0].amount, sol(LAMPORTS_PER_SOL));
+        signatures.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn insufficient_funds() {
+        let mut server = mockito::Server::new_async().await;
+        for (method, value) in [
+            (
+                "getLatestBlockhash",
+                json!({"blockhash": "11111111111111111111111111111111", "lastValidBlockHeight": 100}),
+            ),
+            ("getBalance", json!(LAMPORTS_PER_SOL)),
+            ("getFeeForMessage", json!(5000)),
+        ] {
+            server
+                .mock("POST", "/")
+                .match_body(mockito::Matcher::PartialJson(json!({ "method": method })))
+                .with_header("content-type", "application/json")
+                .with_body(
+                    json!({"jsonrpc": "2.0", "id": 1, "result": {"context": {"slot": 1}, "value": value}})
+                        .to_string(),
+                )
+                .create_async()
+                .await;
+        }
+        let send = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::PartialJson(
+                json!({"method": "sendTransaction"}),
+            ))
+            .expect(0)
+            .create_async()
+            .await;
+        let solana = Solana {
+            net: Net::Local,
+            client: RpcClient::new_with_timeout(server.url(), DEFAULT_TIMEOUT),
+        };
+        let error = solana
+            .transfer(
+                &[7; 32],
+                &RECIPIENT.to_string(),
+                &sol(LAMPORTS_PER_SOL),
+                Commitment::Confirmed,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            WalletError::insufficient_funds(sol(LAMPORTS_PER_SOL + 5000), sol(LAMPORTS_PER_SOL),)
+        );
+        send.assert_async().await;
+    }
+
+    /// Needs a running `solana-test-validator`.
+    #[tokio::test]
+    #[ignore]
+    async fn transfer_token_on_local_validator() {
+        let solana = Solana::new(Net::Local, None, DEFAULT_TIMEOUT).unwrap();
+        let from = Keypair::new();
+        let to = Keypair::new().pubkey();
+        solana
+            .request_airdrop(&from.pubkey().to_string(), &sol(LAMPORTS_PER_SOL))
+            .await
+            .unwrap();
+
+        let mint = Keypair::new();
+        let program = spl_token::id();
+        let source =
+            get_associated_token_address_with_program_id(&from.pubkey(), &mint.pubkey(), &program);
+        let rent = solana
+            .client
+            .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)
+            .await
+            .unwrap();
+        let blockhash = solana.client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                system_instruction::create_account(
+                    &from.pubkey(),
+                    &mint.pubkey(),
+                    rent,
+                    spl_token::state::Mint::LEN as u64,
+                    &program,
+                ),
+                spl_token::instruction::initialize_mint2(
+                    &program,
+                    &mint.pubkey(),
+                    &from.pubkey(),
+                    None,
+                    6,
+                )
+                .unwrap(),
+                create_associated_token_account_idempotent(
+                    &from.pubkey(),
+                    &from.pubkey(),
+                    &mint.pubkey(),
+                    &program,
+                ),
+                spl_token::instruction::mint_to(
+                    &program,
+                    &mint.pubkey(),
+                    &source,
+                    &from.pubkey(),
+                    &[],
+                    1_000_000,
+                )
+                .unwrap(),
+            ],
+            Some(&from.pubkey()),
+            &[&from, &mint],
+            blockhash,
+        );
+        solana
+            .client
+            .send_and_confirm_transaction(&transaction)
+            .await
+            .unwrap();
+
+        let token = Asset::Token {
+            blockchain: Blockchain::Solana,
+            address: mint.pubkey().to_string(),
+            symbol: None,
+        };
+        solana
+            .transfer_token(
+                from.secret_bytes(),
+                &mint.pubkey().to_string(),
+                &to.to_string(),
+                &Amount::new(token, 250_000, 6),
+                Commitment::Confirmed,
+            )
+            .await
+            .unwrap();
+        let balances = solana
+            .get_token_balances(&to.to_string(), &[])
+            .await
+            .unwrap();
+        assert_eq!(balances.len(), 1);
+        assert_eq!(balances[0].token(), mint.pubkey().to_string());
+        assert_eq!(balances[0].amount.units, U256::from(250_000));
+        assert_eq!(
+            solana
+                .get_token_balances(&from.pubkey().to_string(), &[])
+                .await
+                .unwrap()[0]
+                .amount
+                .units,
+            U256::from(750_000)
+        );
+    }
+
+    /// Needs a running `solana-test-validator`.
+    #[tokio::test]
+    #[ignore]
+    async fn transfer_on_local_validator() {
+        let solana = Solana::new(Net::Local, None, DEFAULT_TIMEOUT).unwrap();
+        let from = Keypair::new();
+        let to = Keypair::new().pubkey().to_string();
+        solana
+            .request_airdrop(&from.pubkey().to_string(), &sol(LAMPORTS_PER_SOL))
+            .await
+            .unwrap();
+        assert_eq!(
+            solana
+                .get_balance(&from.pubkey().to_string())
+                .await
+                .unwrap(),
+            sol(LAMPORTS_PER_SOL)
+        );
 
-    fn get_balance(&self, address: &Address) -> ApiResult<u64> {
-        self.client
-            .get_balance(&Pubkey::from_str(address)?)
-            .map_err(|e| e.into())
+        solana
+            .transfer(
+                from.secret_bytes(),
+                &to,
+                &sol(LAMPORTS_PER_SOL / 10),
+                Commitment::Confirmed,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            solana.get_balance(&to).await.unwrap(),
+            sol(LAMPORTS_PER_SOL / 10)
+        );
     }
 }
@@ -1,13 +1,50 @@
-use clap::{Parser, Subcommand};
-use types::shared::{Blockchain, Net};
+use crate::output::Format;
+use crate::secret::SecretSource;
+use clap::{Args, Parser, Subcommand};
+use keypair::{BitcoinAddressType, SolanaDerivation};
+use std::path::PathBuf;
+use types::shared::{Blockchain, Commitment, Net};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 pub(super) struct Cli {
+    /// Directory where wallets and the config are saved [default: $WALLET_DATA_DIR, or the
+    /// platform data directory for wallets and config directory for the config].
+    #[arg(long, global = true)]
+    pub(super) data_dir: Option<PathBuf>,
+    /// Seconds a node or API server has to answer each request.
+    #[arg(long, global = true, default_value_t = 30)]
+    pub(super) timeout: u64,
+    /// Network of the config to connect to, e.g. a custom network [default: the network of the wallet].
+    #[arg(long, global = true)]
+    pub(super) network: Option<String>,
+    /// Endpoint of a blockchain used instead of the configured one, e.g.
+    /// `solana=http://localhost:18899`. Can be repeated.
+    #[arg(long, global = true, value_parser = parse_rpc_url)]
+    pub(super) rpc_url: Vec<(Blockchain, String)>,
+    /// Header sent to the endpoint of a blockchain, e.g. `ethereum=Authorization: Bearer <key>`.
+    /// Can be repeated.
+    #[arg(long, global = true, value_parser = parse_rpc_header)]
+    pub(super) rpc_header: Vec<(Blockchain, String, String)>,
+    /// Print results as text, or as JSON for scripts, with errors as JSON on stderr.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub(super) output: Format,
     #[command(subcommand)]
     pub(super) command: Option<Commands>,
 }
 
+/// Exports the secrets of a new or restored wallet.
+#[derive(Args)]
+pub(super) struct ExportArgs {
+    /// Show the seed phrase, passphrase and private keys after a confirmation, or write them to
+    /// a new file readable only by its owner.
+    #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "-")]
+    pub(super) export_secrets: Option<PathBuf>,
+    /// Export without asking for confirmation.
+    #[arg(long)]
+    pub(super) yes: bool,
+}
+
 #[derive(Subcommand)]
 pub(super) enum Commands {
     /// Create a new wallet.
@@ -16,15 +53,248 @@ pub(super) enum Commands {
         net: Option<Net>,
         language_code: Option<String>,
         word_count: Option<i32>,
-        passphrase: Option<String>,
+        /// Protect the seed with a BIP-39 passphrase, read from `prompt` (the default when the
+        /// flag has no value), `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", num_args = 0..=1, default_missing_value = "prompt")]
+        passphrase: Option<SecretSource>,
+        /// Bitcoin address type: p2pkh (BIP-44), p2sh-p2wpkh (BIP-49), p2wpkh (BIP-84) or p2tr (BIP-86).
+        #[arg(long, default_value_t)]
+        bitcoin_address_type: BitcoinAddressType,
+        /// Save the wallet to the keystore, encrypted with a password read from `prompt` (the
+        /// default when the flag has no value), `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", num_args = 0..=1, default_missing_value = "prompt")]
+        password: Option<SecretSource>,
+        #[command(flatten)]
+        export: ExportArgs,
     },
     /// Restore a wallet from a seed phrase.
     Restore {
         name: String,
         net: Net,
         language_code: String,
-        seed_phrase: String,
-        passphrase: Option<String>,
+        /// Read the seed phrase from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        seed_phrase: SecretSource,
+        /// Protect the seed with a BIP-39 passphrase, read from `prompt` (the default when the
+        /// flag has no value), `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", num_args = 0..=1, default_missing_value = "prompt")]
+        passphrase: Option<SecretSource>,
+        /// Bitcoin address type: p2pkh (BIP-44), p2sh-p2wpkh (BIP-49), p2wpkh (BIP-84) or p2tr (BIP-86).
+        #[arg(long, default_value_t)]
+        bitcoin_address_type: BitcoinAddressType,
+        /// Solana derivation: `bip44`, a fixed derivation path, or `legacy` for addresses
+        /// created by earlier versions.
+        #[arg(long, default_value_t)]
+        solana_derivation: SolanaDerivation,
+        /// Save the wallet to the keystore, encrypted with a password read from `prompt` (the
+        /// default when the flag has no value), `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", num_args = 0..=1, default_missing_value = "prompt")]
+        password: Option<SecretSource>,
+        #[command(flatten)]
+        export: ExportArgs,
+    },
+    /// Check a seed phrase: suggest words of the wordlist for the unknown ones, and list the
+    /// words that make the checksum valid when a single word is unknown, missing or wrong.
+    CheckMnemonic {
+        language_code: Option<String>,
+        /// Read the seed phrase from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        seed_phrase: SecretSource,
+        /// Number of candidate words shown as text, 0 for all. JSON lists them all.
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// List the saved wallets.
+    List,
+    /// Show the addresses of a saved wallet.
+    Show {
+        name: String,
+        /// Read the keystore password from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        password: SecretSource,
+    },
+    /// Export the seed phrase, passphrase and private keys of a saved wallet, after a
+    /// confirmation.
+    ExportSecrets {
+        name: String,
+        /// Read the keystore password from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        password: SecretSource,
+        /// Write the secrets to this new file, readable only by its owner, instead of the
+        /// terminal.
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Export without asking for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Derive an address of a saved wallet and remember it.
+    Address {
+        name: String,
+        /// Read the keystore password from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        password: SecretSource,
+        /// Only derive the address of this blockchain.
+        #[arg(long)]
+        blockchain: Option<Blockchain>,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        #[arg(long, default_value_t = 0)]
+        index: u32,
+        /// Derive a change address.
+        #[arg(long)]
+        change: bool,
+    },
+    /// Export the extended public key of a Bitcoin account of a saved wallet.
+    Xpub {
+        name: String,
+        /// Read the keystore password from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        password: SecretSource,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+    },
+    /// List the first receive and change addresses of a saved wallet.
+    Addresses {
+        name: String,
+        /// Read the keystore password from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        password: SecretSource,
+        /// Only list the addresses of this blockchain.
+        #[arg(long)]
+        blockchain: Option<Blockchain>,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        #[arg(long, default_value_t = 5)]
+        count: u32,
+    },
+    /// Watch the addresses of a Bitcoin extended public key or output descriptor, or of a
+    /// Solana public key, without any secret.
+    Watch {
+        key: String,
+        net: Net,
+        /// Script type of a plain xpub/tpub, which doesn't encode it [default: p2pkh].
+        #[arg(long)]
+        bitcoin_address_type: Option<BitcoinAddressType>,
+        #[arg(long, default_value_t = 5)]
+        count: u32,
+        /// Also query the balance of each address.
+        #[arg(long)]
+        balances: bool,
+    },
+    /// Send coins from a saved wallet: from an address on Solana, from the UTXOs of an
+    /// account on Bitcoin.
+    Send {
+        blockchain: Blockchain,
+        to: String,
+        /// Amount in the native coin, e.g. SOL.
+        amount: String,
+        /// Name of the saved wallet to send from.
+        #[arg(long)]
+        wallet: String,
+        /// Read the keystore password from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        password: SecretSource,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        #[arg(long, default_value_t = 0)]
+        index: u32,
+        /// Wait until the transaction is processed, confirmed or finalized (Solana).
+        #[arg(long, default_value_t)]
+        commitment: Commitment,
+        /// Fee rate in sat/vB [default: estimated for confirmation within 6 blocks] (Bitcoin).
+        /// Rates above 1000 sat/vB ask for a confirmation.
+        #[arg(long, value_parser = parse_fee_rate)]
+        fee_rate: Option<f64>,
+        /// Send a fee rate above 1000 sat/vB without asking for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Show the balances of all the addresses of a saved wallet, with a total per blockchain.
+    Portfolio {
+        name: String,
+        /// Read the keystore password from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        password: SecretSource,
+    },
+    /// List the past transactions of an address of a saved wallet, newest first.
+    History {
+        name: String,
+        /// Read the keystore password from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        password: SecretSource,
+        blockchain: Blockchain,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        #[arg(long, default_value_t = 0)]
+        index: u32,
+        /// Number of transactions per page.
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        /// Show the page of transactions older than this one.
+        #[arg(long)]
+        before: Option<String>,
+    },
+    /// List the token balances of an address of a saved wallet: its SPL tokens on Solana, the
+    /// ERC-20 tokens of the config on Ethereum.
+    Tokens {
+        name: String,
+        /// Read the keystore password from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        password: SecretSource,
+        /// Only list the tokens of this blockchain.
+        #[arg(long)]
+        blockchain: Option<Blockchain>,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        #[arg(long, default_value_t = 0)]
+        index: u32,
+    },
+    /// Send tokens from an address of a saved wallet. On Solana, the token account of the
+    /// recipient is created if it doesn't exist yet.
+    SendToken {
+        blockchain: Blockchain,
+        /// Token mint (Solana) or contract (Ethereum) address.
+        token: String,
+        to: String,
+        /// Amount in whole tokens, e.g. 12.5.
+        amount: String,
+        /// Name of the saved wallet to send from.
+        #[arg(long)]
+        wallet: String,
+        /// Read the keystore password from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        password: SecretSource,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        #[arg(long, default_value_t = 0)]
+        index: u32,
+        /// Wait until the transaction is processed, confirmed or finalized.
+        #[arg(long, default_value_t)]
+        commitment: Commitment,
+    },
+    /// Add a token to the list of the config, whose balances `tokens` shows.
+    AddToken {
+        net: Net,
+        blockchain: Blockchain,
+        /// Token contract (Ethereum) or mint (Solana) address.
+        address: String,
+        #[arg(long)]
+        symbol: Option<String>,
+    },
+    /// Request test SOL for an address of a saved wallet, on the test, dev or local networks.
+    Airdrop {
+        name: String,
+        /// Read the keystore password from `prompt`, `stdin`, `fd:<n>` or `env:<VAR>`.
+        #[arg(long, value_name = "SOURCE", default_value_t = SecretSource::Prompt)]
+        password: SecretSource,
+        /// Amount in SOL.
+        #[arg(default_value = "1")]
+        amount: String,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        #[arg(long, default_value_t = 0)]
+        index: u32,
     },
     /// Get a wallet balance.
     Balance {
@@ -33,3 +303,51 @@ pub(super) enum Commands {
         net: Option<Net>,
     },
 }
+
+/// Fee rates in sat/vB above this one are most likely typos, and need a confirmation.
+pub(super) const MAX_FEE_RATE: f64 = 1000.0;
+
+/// Parses a positive fee rate in sat/vB.
+fn parse_fee_rate(input: &str) -> Result<f64, String> {
+    match input.parse::<f64>() {
+        Ok(fee_rate) if fee_rate.is_finite() && fee_rate > 0.0 => Ok(fee_rate),
+        _ => Err(format!(
+            "Invalid fee rate {input}, expected a positive number of sat/vB"
+        )),
+    }
+}
+
+/// Parses `<blockchain>=<url>`.
+fn parse_rpc_url(input: &str) -> Result<(Blockchain, String), String> {
+    let (blockchain, url) = input.split_once('=').ok_or("Expected <blockchain>=<url>")?;
+    Ok((
+        blockchain.parse().map_err(|e| format!("{e}"))?,
+        url.to_string(),
+    ))
+}
+
+/// Parses `<blockchain>=<name>: <value>`.
+fn parse_rpc_header(input: &str) -> Result<(Blockchain, String, String), String> {
+    let (blockchain, url) = parse_rpc_url(input)?;
+    let (name, value) = url
+        .split_once(':')
+        .ok_or("Expected <blockchain>=<name>: <value>")?;
+    Ok((
+        blockchain,
+        name.trim().to_string(),
+        value.trim().to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fee_rates() {
+        assert_eq!(parse_fee_rate("2.5"), Ok(2.5));
+        for invalid in ["0", "-1", "NaN", "inf", "5 sat/vB"] {
+            assert!(parse_fee_rate(invalid).is_err(), "{invalid}");
+        }
+    }
+}
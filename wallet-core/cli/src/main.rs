@@ -1,14 +1,64 @@
-use crate::cli::{Cli, Commands};
-use api::client::Api;
+use crate::cli::{Cli, Commands, ExportArgs, MAX_FEE_RATE};
+use crate::output::{error_json, Format};
+use crate::secret::{confirm, read_password, read_secret, SecretSource, Terminal};
+use api::client::{Api, Direction, TransactionStatus, TransactionSummary};
+use api::endpoint::Endpoint;
+use chrono::DateTime;
 use clap::Parser;
+use keypair::account::AddressIndex;
+use keypair::Derivation;
+use mnemonic::{Candidate, Mnemonic, MnemonicCheck, MnemonicStatus, Repair};
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
 use std::error;
-use types::shared::Net;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use types::amount::Amount;
+use types::error::WalletError;
+use types::shared::{Address, Blockchain, Commitment, Net};
+use wallet::config::{Config, Token};
+use wallet::keystore::Keystore;
 use wallet::wallet::Wallet;
+use zeroize::Zeroizing;
 
 mod cli;
+mod output;
+mod secret;
 
-fn main() -> Result<(), Box<dyn error::Error>> {
-    let cli = Cli::parse();
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        // Help and version go to stdout, and text errors keep the formatting of clap.
+        Err(error)
+            if error.use_stderr() && Format::from_args(std::env::args_os()) == Format::Json =>
+        {
+            return Format::Json.fail(&error);
+        }
+        Err(error) => error.exit(),
+    };
+    match run(&cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => cli.output.fail(error.as_ref()),
+    }
+}
+
+async fn run(cli: &Cli) -> Result<(), Box<dyn error::Error>> {
+    let format = cli.output;
+    let keystore = cli
+        .data_dir
+        .as_ref()
+        .map(|dir| Keystore::new(dir.join("keystore")))
+        .unwrap_or_default();
+    let config_path = cli
+        .data_dir
+        .as_ref()
+        .map(|dir| dir.join("config.toml"))
+        .unwrap_or_else(Config::default_path);
+    // The config is only loaded by the commands that use it, so that a broken config doesn't
+    // lock the user out of their wallets.
+    let api = |net| connect(cli, &Config::load(&config_path)?, net);
 
     match &cli.command {
         Some(Commands::New {
@@ -17,15 +67,28 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             language_code,
             word_count,
             passphrase,
+            bitcoin_address_type,
+            password,
+            export,
         }) => {
+            let passphrase = match passphrase {
+                Some(source) => read_secret(&mut Terminal, source, "passphrase")?,
+                None => Zeroizing::default(),
+            };
             let wallet = Wallet::new(
                 name,
                 net.unwrap_or(Net::Dev),
                 &language_code.clone().unwrap_or("en".to_string()),
                 word_count.unwrap_or(12),
-                &passphrase.clone().unwrap_or("".to_string()),
+                &passphrase,
+                &Derivation {
+                    bitcoin: *bitcoin_address_type,
+                    ..Derivation::default()
+                },
             )?;
-            println!("{wallet}")
+            let saved = save(&keystore, &wallet, password)?;
+            let exported = export_new(&wallet, export)?;
+            print_new_wallet(format, &wallet, saved, exported.as_ref())?;
         }
         Some(Commands::Restore {
             name,
@@ -33,33 +96,1069 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             language_code,
             seed_phrase,
             passphrase,
+            bitcoin_address_type,
+            solana_derivation,
+            password,
+            export,
         }) => {
+            let seed_phrase = read_secret(&mut Terminal, seed_phrase, "seed phrase")?;
+            let passphrase = match passphrase {
+                Some(source) => read_secret(&mut Terminal, source, "passphrase")?,
+                None => Zeroizing::default(),
+            };
             let wallet = Wallet::restore_from_seed(
                 name,
                 *net,
                 &language_code.clone(),
-                &seed_phrase.clone(),
-                &passphrase.clone().unwrap_or("".to_string()),
+                &seed_phrase,
+                &passphrase,
+                &Derivation {
+                    bitcoin: *bitcoin_address_type,
+                    solana: solana_derivation.clone(),
+                },
             )?;
-            println!("{wallet}")
+            let saved = save(&keystore, &wallet, password)?;
+            let exported = export_new(&wallet, export)?;
+            print_new_wallet(format, &wallet, saved, exported.as_ref())?;
+        }
+        Some(Commands::CheckMnemonic {
+            language_code,
+            seed_phrase,
+            limit,
+        }) => {
+            let seed_phrase = read_secret(&mut Terminal, seed_phrase, "seed phrase")?;
+            let check = Mnemonic::check(language_code.as_deref().unwrap_or("en"), &seed_phrase);
+            // An invalid phrase is printed with its problem and candidates, then fails without
+            // its words.
+            format.print(mnemonic_check_json(&check), || {
+                println!("{}", check.details());
+                print_candidates(&check.candidates, *limit);
+            });
+            if !check.is_valid() {
+                return Err(WalletError::InvalidMnemonic(check.to_string()).into());
+            }
         }
         Some(Commands::Balance {
             blockchain,
             address,
             net,
         }) => {
-            let api = Api::new(*blockchain, net.unwrap_or(Net::Dev));
-            match api.get_balance(address) {
-                Ok(balance) => {
-                    println!("Balance for {address}: {balance}")
-                }
-                Err(e) => {
-                    eprintln!("Error getting balance: {e}")
-                }
+            let net = match (net, &cli.network) {
+                (Some(net), _) => *net,
+                (None, Some(network)) => Config::load(&config_path)?.net(network)?,
+                (None, None) => Net::Dev,
+            };
+            let api = api(net)?;
+            let balance = api.get_balance(*blockchain, address).await?;
+            format.print(balance_json(*blockchain, address, &balance), || {
+                println!("Balance for {address}: {balance}")
+            });
+        }
+        Some(Commands::List) => {
+            let names = keystore.list()?;
+            format.print(json!({ "wallets": names }), || {
+                names.iter().for_each(|name| println!("{name}"))
+            });
+        }
+        Some(Commands::Show { name, password }) => {
+            let wallet = load(&keystore, name, password)?;
+            format.print(wallet_json(&wallet)?, || println!("{wallet}"));
+        }
+        Some(Commands::ExportSecrets {
+            name,
+            password,
+            file,
+            yes,
+        }) => {
+            let wallet = load(&keystore, name, password)?;
+            let exported = export_secrets(&wallet, file.as_deref(), *yes)?;
+            format.print(
+                ExportJson {
+                    wallet: wallet.name(),
+                    secrets: &exported,
+                },
+                || exported.print(&wallet),
+            );
+        }
+        Some(Commands::Address {
+            name,
+            password,
+            blockchain,
+            account,
+            index,
+            change,
+        }) => {
+            let mut wallet = load(&keystore, name, password)?;
+            let index = AddressIndex::new(*account, *change, *index);
+            let addresses = blockchains(blockchain)
+                .iter()
+                .map(|blockchain| (*blockchain, wallet.open_address(*blockchain, index)))
+                .collect::<Vec<_>>();
+            wallet.save_opened(&keystore)?;
+            format.print(
+                json!({
+                    "addresses": addresses
+                        .iter()
+                        .map(|(blockchain, address)| {
+                            let json = json!({ "blockchain": blockchain, "index": index });
+                            with_result(json, "address", address)
+                        })
+                        .collect::<Vec<_>>(),
+                }),
+                || {
+                    for (blockchain, address) in &addresses {
+                        match address {
+                            Ok(address) => println!("{blockchain} [{index}]: {address}"),
+                            Err(e) => eprintln!("{blockchain} [{index}]: {e}"),
+                        }
+                    }
+                },
+            );
+        }
+        Some(Commands::Addresses {
+            name,
+            password,
+            blockchain,
+            account,
+            count,
+        }) => {
+            let wallet = load(&keystore, name, password)?;
+            let addresses = blockchains(blockchain)
+                .iter()
+                .map(|blockchain| {
+                    (
+                        *blockchain,
+                        wallet.list_addresses(*blockchain, *account, *count),
+                    )
+                })
+                .collect::<Vec<_>>();
+            format.print(
+                json!({
+                    "addresses": addresses
+                        .iter()
+                        .flat_map(|(blockchain, addresses)| match addresses {
+                            Ok(addresses) => addresses
+                                .iter()
+                                .map(|(index, address)| address_json(*blockchain, *index, address))
+                                .collect(),
+                            Err(e) => {
+                                vec![json!({ "blockchain": blockchain, "error": error_json(e) })]
+                            }
+                        })
+                        .collect::<Vec<_>>(),
+                }),
+                || {
+                    for (blockchain, addresses) in &addresses {
+                        match addresses {
+                            Ok(addresses) => addresses.iter().for_each(|(index, address)| {
+                                println!("{blockchain} [{index}]: {address}")
+                            }),
+                            Err(e) => eprintln!("{blockchain}: {e}"),
+                        }
+                    }
+                },
+            );
+        }
+        Some(Commands::Xpub {
+            name,
+            password,
+            account,
+        }) => {
+            let wallet = load(&keystore, name, password)?;
+            let xpub = wallet.account()?.bitcoin_xpub(*account)?;
+            format.print(
+                json!({
+                    "path": xpub.path().map(|path| path.to_string()),
+                    "fingerprint": xpub.fingerprint().map(|fingerprint| fingerprint.to_string()),
+                    "xpub": xpub.slip132(),
+                    "receive_descriptor": xpub.descriptor(false),
+                    "change_descriptor": xpub.descriptor(true),
+                }),
+                || {
+                    if let (Some(path), Some(fingerprint)) = (xpub.path(), xpub.fingerprint()) {
+                        println!("Path: {path}");
+                        println!("Fingerprint: {fingerprint}");
+                    }
+                    println!("Extended public key: {}", xpub.slip132());
+                    println!("Receive descriptor: {}", xpub.descriptor(false));
+                    println!("Change descriptor: {}", xpub.descriptor(true));
+                },
+            );
+        }
+        Some(Commands::Watch {
+            key,
+            net,
+            bitcoin_address_type,
+            count,
+            balances,
+        }) => {
+            let wallet = Wallet::watch_only("watch-only", *net, key, *bitcoin_address_type)?;
+            let blockchain = wallet
+                .watch_only_account()
+                .ok_or("Not a watch-only wallet")?
+                .blockchain();
+            let addresses = wallet.list_addresses(blockchain, 0, *count)?;
+            let results = if *balances {
+                let queries = addresses
+                    .iter()
+                    .map(|(_, address)| (blockchain, address.clone()))
+                    .collect::<Vec<_>>();
+                Some(api(*net)?.get_balances(&queries).await)
+            } else {
+                None
+            };
+            format.print(
+                json!({
+                    "wallet": wallet_json(&wallet)?,
+                    "addresses": addresses
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (index, address))| {
+                            let json = address_json(blockchain, *index, address);
+                            match &results {
+                                Some(results) => with_result(json, "balance", &results[i]),
+                                None => json,
+                            }
+                        })
+                        .collect::<Vec<_>>(),
+                }),
+                || {
+                    println!("{wallet}");
+                    for (i, (index, address)) in addresses.iter().enumerate() {
+                        match results.as_ref().map(|results| &results[i]) {
+                            Some(Ok(balance)) => {
+                                println!("{blockchain} [{index}]: {address} {balance}")
+                            }
+                            Some(Err(e)) => eprintln!("{blockchain} [{index}]: {address} {e}"),
+                            None => println!("{blockchain} [{index}]: {address}"),
+                        }
+                    }
+                },
+            );
+        }
+        Some(Commands::Send {
+            blockchain,
+            to,
+            amount,
+            wallet,
+            password,
+            account,
+            index,
+            commitment,
+            fee_rate,
+            yes,
+        }) => {
+            if let Some(fee_rate) = fee_rate.filter(|&fee_rate| fee_rate > MAX_FEE_RATE) {
+                confirm(
+                    &mut Terminal,
+                    &format!("Pay a fee rate of {fee_rate} sat/vB, above {MAX_FEE_RATE} sat/vB?"),
+                    *yes,
+                )?;
             }
+            let mut wallet = load(&keystore, wallet, password)?;
+            let amount = Amount::parse_native(*blockchain, amount)?;
+            if *blockchain == Blockchain::Bitcoin {
+                let txid = wallet
+                    .send_bitcoin(
+                        &api(wallet.net())?,
+                        *account,
+                        to,
+                        amount.to_u64()?,
+                        *fee_rate,
+                    )
+                    .await?;
+                wallet.save_opened(&keystore)?;
+                format.print(sent_json(to, &amount, &txid, None), || {
+                    println!("Sent {amount} to {to}: {txid}")
+                });
+            } else {
+                let index = AddressIndex::new(*account, false, *index);
+                let signature = wallet
+                    .transfer(&api(wallet.net())?, index, to, &amount, *commitment)
+                    .await?;
+                format.print(
+                    sent_json(to, &amount, &signature, Some(*commitment)),
+                    || println!("Sent {amount} to {to} ({commitment}): {signature}"),
+                );
+            }
+        }
+        Some(Commands::Portfolio { name, password }) => {
+            let wallet = load(&keystore, name, password)?;
+            let portfolio = wallet.balances(&api(wallet.net())?).await?;
+            format.print(
+                json!({
+                    "blockchains": portfolio
+                        .blockchains()
+                        .into_iter()
+                        .map(|blockchain| {
+                            let json = json!({
+                                "blockchain": blockchain,
+                                "complete": !portfolio.is_incomplete(blockchain),
+                                "addresses": portfolio
+                                    .addresses(blockchain)
+                                    .map(|address| {
+                                        let json = address_json(
+                                            blockchain,
+                                            address.index,
+                                            &address.address,
+                                        );
+                                        with_result(json, "balance", &address.balance)
+                                    })
+                                    .collect::<Vec<_>>(),
+                            });
+                            with_result(json, "total", &portfolio.total(blockchain))
+                        })
+                        .collect::<Vec<_>>(),
+                }),
+                || {
+                    for blockchain in portfolio.blockchains() {
+                        match portfolio.total(blockchain) {
+                            Ok(total) if portfolio.is_incomplete(blockchain) => {
+                                println!("{blockchain}: at least {total}")
+                            }
+                            Ok(total) => println!("{blockchain}: {total}"),
+                            Err(e) => println!("{blockchain}: {e}"),
+                        }
+                        for address in portfolio.addresses(blockchain) {
+                            match &address.balance {
+                                Ok(balance) => {
+                                    println!("  [{}] {}: {balance}", address.index, address.address)
+                                }
+                                Err(e) => {
+                                    eprintln!("  [{}] {}: {e}", address.index, address.address)
+                                }
+                            }
+                        }
+                    }
+                },
+            );
+        }
+        Some(Commands::History {
+            name,
+            password,
+            blockchain,
+            account,
+            index,
+            limit,
+            before,
+        }) => {
+            let wallet = load(&keystore, name, password)?;
+            let index = AddressIndex::new(*account, false, *index);
+            let transactions = wallet
+                .transactions(
+                    &api(wallet.net())?,
+                    *blockchain,
+                    index,
+                    *limit,
+                    before.as_deref(),
+                )
+                .await?;
+            // Esplora only pages from a confirmed transaction, listed after the pending ones.
+            let older = transactions
+                .iter()
+                .rev()
+                .find(|transaction| {
+                    *blockchain != Blockchain::Bitcoin
+                        || transaction.status != TransactionStatus::Pending
+                })
+                .filter(|_| transactions.len() == *limit)
+                .map(|last| &last.id);
+            format.print(
+                history_json(&transactions, older.map(String::as_str)),
+                || {
+                    for transaction in &transactions {
+                        let time = transaction
+                            .timestamp
+                            .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+                            .map_or("pending".to_string(), |time| {
+                                time.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+                            });
+                        let counterparty = match (transaction.direction, &transaction.counterparty)
+                        {
+                            (Direction::Incoming, Some(from)) => format!(" from {from}"),
+                            (_, Some(to)) => format!(" to {to}"),
+                            (_, None) => String::new(),
+                        };
+                        println!(
+                            "{time} {} {}{counterparty}, fee {} ({}): {}",
+                            transaction.direction,
+                            transaction.amount,
+                            transaction.fee,
+                            transaction.status,
+                            transaction.id
+                        );
+                    }
+                    if let Some(older) = older {
+                        println!("Older transactions: --before {older}");
+                    }
+                },
+            );
+        }
+        Some(Commands::Tokens {
+            name,
+            password,
+            blockchain,
+            account,
+            index,
+        }) => {
+            let wallet = load(&keystore, name, password)?;
+            let config = Config::load(&config_path)?;
+            let api = connect(cli, &config, wallet.net())?;
+            let index = AddressIndex::new(*account, false, *index);
+            let mut results = vec![];
+            for blockchain in blockchains(blockchain) {
+                let tokens = config.tokens(wallet.net(), *blockchain);
+                let balances = wallet
+                    .token_balances(&api, *blockchain, index, &tokens)
+                    .await;
+                results.push((*blockchain, balances));
+            }
+            format.print(
+                json!({
+                    "balances": results
+                        .iter()
+                        .flat_map(|(blockchain, balances)| match balances {
+                            Ok(balances) => balances
+                                .iter()
+                                .map(|balance| {
+                                    json!({
+                                        "blockchain": blockchain,
+                                        "index": index,
+                                        "token": balance.token(),
+                                        "account": balance.account,
+                                        "amount": balance.amount,
+                                    })
+                                })
+                                .collect(),
+                            Err(e) => vec![json!({
+                                "blockchain": blockchain,
+                                "index": index,
+                                "error": error_json(e),
+                            })],
+                        })
+                        .collect::<Vec<_>>(),
+                }),
+                || {
+                    for (blockchain, balances) in &results {
+                        match balances {
+                            Ok(balances) => balances.iter().for_each(|balance| {
+                                println!(
+                                    "{blockchain} [{index}]: {} {} {} ({} decimals)",
+                                    balance.token(),
+                                    balance.amount.ui_amount(),
+                                    balance.amount.asset.symbol().unwrap_or_default(),
+                                    balance.amount.decimals
+                                )
+                            }),
+                            Err(e) => eprintln!("{blockchain} [{index}]: {e}"),
+                        }
+                    }
+                },
+            );
+        }
+        Some(Commands::SendToken {
+            blockchain,
+            token,
+            to,
+            amount,
+            wallet,
+            password,
+            account,
+            index,
+            commitment,
+        }) => {
+            let wallet = load(&keystore, wallet, password)?;
+            let api = api(wallet.net())?;
+            let index = AddressIndex::new(*account, false, *index);
+            let balance = wallet
+                .token_balances(&api, *blockchain, index, std::slice::from_ref(token))
+                .await?
+                .into_iter()
+                .find(|balance| balance.token() == token)
+                .ok_or(format!(
+                    "No balance of token {token} at {blockchain} [{index}]"
+                ))?;
+            let amount = Amount::parse(balance.amount.asset, balance.amount.decimals, amount)?;
+            let signature = wallet
+                .transfer(&api, index, to, &amount, *commitment)
+                .await?;
+            format.print(
+                sent_json(to, &amount, &signature, Some(*commitment)),
+                || println!("Sent {amount} to {to} ({commitment}): {signature}"),
+            );
+        }
+        Some(Commands::AddToken {
+            net,
+            blockchain,
+            address,
+            symbol,
+        }) => {
+            let token = Token {
+                net: *net,
+                blockchain: *blockchain,
+                address: address.clone(),
+                symbol: symbol.clone(),
+            };
+            let mut config = Config::load(&config_path)?;
+            let added = config.add_token(token.clone());
+            if added {
+                config.save(&config_path)?;
+            }
+            format.print(
+                json!({ "token": token, "added": added, "config": config_path }),
+                || {
+                    if added {
+                        println!("Added {address} to {}", config_path.display());
+                    } else {
+                        println!("{address} is already listed in {}", config_path.display());
+                    }
+                },
+            );
+        }
+        Some(Commands::Airdrop {
+            name,
+            password,
+            amount,
+            account,
+            index,
+        }) => {
+            let wallet = load(&keystore, name, password)?;
+            let amount = Amount::parse_native(Blockchain::Solana, amount)?;
+            let index = AddressIndex::new(*account, false, *index);
+            let signature = wallet
+                .request_airdrop(&api(wallet.net())?, index, &amount)
+                .await?;
+            let address = wallet.derive_address(Blockchain::Solana, index)?;
+            format.print(sent_json(&address, &amount, &signature, None), || {
+                println!("Airdropped {amount} to {address}: {signature}")
+            });
         }
         None => {}
     }
 
     Ok(())
 }
+
+/// Connects to `net` through the endpoints that the config has for `--network`, or for `net`
+/// itself, overridden by `--rpc-url` and `--rpc-header`.
+fn connect(cli: &Cli, config: &Config, net: Net) -> Result<Api, WalletError> {
+    let network = cli.network.as_deref().unwrap_or(net.name());
+    if config.net(network)? != net {
+        return Err(WalletError::WrongNetwork(format!(
+            "Network {network} is not a {net} network"
+        )));
+    }
+    let mut api_config = config.api_config(network);
+    api_config.timeout = Duration::from_secs(cli.timeout);
+    for (blockchain, url) in &cli.rpc_url {
+        api_config
+            .endpoints
+            .insert(*blockchain, Endpoint::new(url.clone()));
+    }
+    for (blockchain, name, value) in &cli.rpc_header {
+        api_config
+            .endpoints
+            .get_mut(blockchain)
+            .ok_or_else(|| {
+                WalletError::InvalidInput(format!(
+                    "--rpc-header needs an endpoint for {blockchain}, from --rpc-url or the config"
+                ))
+            })?
+            .headers
+            .insert(name.clone(), value.clone());
+    }
+    Api::new(net, &api_config)
+}
+
+fn blockchains(blockchain: &Option<Blockchain>) -> &[Blockchain] {
+    match blockchain {
+        Some(blockchain) => std::slice::from_ref(blockchain),
+        None => Blockchain::all_variants(),
+    }
+}
+
+/// `{"blockchain": "solana", "index": {"account": 0, "change": false, "index": 0}, "address": "..."}`
+fn address_json(blockchain: Blockchain, index: AddressIndex, address: &str) -> Value {
+    json!({ "blockchain": blockchain, "index": index, "address": address })
+}
+
+/// The public view of a wallet: `{"name", "net", "watch_only", "addresses"}`.
+fn wallet_json(wallet: &Wallet) -> Result<Value, WalletError> {
+    Ok(json!({
+        "name": wallet.name(),
+        "net": wallet.net(),
+        "watch_only": wallet.is_watch_only(),
+        "addresses": wallet
+            .addresses()?
+            .iter()
+            .map(|(blockchain, index, address)| address_json(*blockchain, *index, address))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+/// `{"blockchain": "solana", "address": "...", "balance": <amount>}`
+fn balance_json(blockchain: Blockchain, address: &str, balance: &Amount) -> Value {
+    json!({ "blockchain": blockchain, "address": address, "balance": balance })
+}
+
+/// `{"to", "amount", "id", "commitment"}` of a sent transaction, with the commitment it was
+/// waited for if the blockchain has one.
+fn sent_json(to: &Address, amount: &Amount, id: &str, commitment: Option<Commitment>) -> Value {
+    let mut json = json!({ "to": to, "amount": amount, "id": id });
+    if let Some(commitment) = commitment {
+        json["commitment"] = json!(commitment.to_string());
+    }
+    json
+}
+
+/// `{"transactions": [...], "before": <cursor of the next page> or null}`
+fn history_json(transactions: &[TransactionSummary], before: Option<&str>) -> Value {
+    json!({ "transactions": transactions, "before": before })
+}
+
+/// `{"valid": false, "status": "unknown_words", "word_count": 12, "unknown_words": [{"position":
+/// 2, "word": "fsosil", "suggestions": ["fossil", "foil"]}], "candidates": [{"position": 2,
+/// "word": "fossil", "repair": "replace"}, ...]}`
+fn mnemonic_check_json(check: &MnemonicCheck) -> Value {
+    let status = match check.status {
+        MnemonicStatus::Valid => "valid",
+        MnemonicStatus::UnknownWords => "unknown_words",
+        MnemonicStatus::MissingWord => "missing_word",
+        MnemonicStatus::WrongWordCount => "wrong_word_count",
+        MnemonicStatus::InvalidChecksum => "invalid_checksum",
+    };
+    json!({
+        "valid": check.is_valid(),
+        "status": status,
+        "word_count": check.word_count,
+        "unknown_words": check
+            .unknown_words
+            .iter()
+            .map(|unknown| json!({
+                "position": unknown.position,
+                "word": unknown.word,
+                "suggestions": unknown.suggestions,
+            }))
+            .collect::<Vec<_>>(),
+        "candidates": check
+            .candidates
+            .iter()
+            .map(|candidate| json!({
+                "position": candidate.position,
+                "word": candidate.word,
+                "repair": match candidate.repair {
+                    Repair::Replace => "replace",
+                    Repair::Insert => "insert",
+                },
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Prints the first `limit` candidates of a seed phrase check, or all of them when `limit` is 0.
+fn print_candidates(candidates: &[Candidate], limit: usize) {
+    if candidates.is_empty() {
+        return;
+    }
+    let limit = if limit == 0 { candidates.len() } else { limit };
+    println!("Words that make the checksum valid:");
+    for candidate in candidates.iter().take(limit) {
+        match candidate.repair {
+            Repair::Replace => println!("  word {}: {}", candidate.position, candidate.word),
+            Repair::Insert => println!(
+                "  insert as word {}: {}",
+                candidate.position, candidate.word
+            ),
+        }
+    }
+    if candidates.len() > limit {
+        println!("  ... and {} more", candidates.len() - limit);
+    }
+}
+
+/// Adds a result to the JSON object `json`: its value as `key`, or its error as `error`.
+fn with_result<T: Serialize>(mut json: Value, key: &str, result: &Result<T, WalletError>) -> Value {
+    match result {
+        Ok(value) => json[key] = json!(value),
+        Err(error) => json["error"] = error_json(error),
+    }
+    json
+}
+
+/// Prints a new or restored wallet, where it was saved, and its exported secrets.
+fn print_new_wallet(
+    format: Format,
+    wallet: &Wallet,
+    saved: Option<&Path>,
+    exported: Option<&Exported>,
+) -> Result<(), WalletError> {
+    format.print(
+        NewWalletJson {
+            wallet: wallet_json(wallet)?,
+            keystore: saved,
+            secrets: exported,
+        },
+        || {
+            if let Some(keystore) = saved {
+                println!(
+                    "Wallet {} saved to {}\n",
+                    wallet.name(),
+                    keystore.display()
+                );
+            }
+            println!("{wallet}");
+            match exported {
+                Some(exported) => exported.print(wallet),
+                None if saved.is_none() => eprintln!(
+                    "The wallet isn't saved: keep it with --password, or back up its seed phrase with --export-secrets"
+                ),
+                None => {}
+            }
+        },
+    );
+    Ok(())
+}
+
+/// `{"wallet", "keystore", "secrets"}` of a new or restored wallet. Unlike a [`Value`], it
+/// doesn't copy the exported secrets.
+#[derive(Serialize)]
+struct NewWalletJson<'a> {
+    wallet: Value,
+    keystore: Option<&'a Path>,
+    secrets: Option<&'a Exported>,
+}
+
+/// `{"wallet": "<name>", "secrets"}` of `export-secrets`.
+#[derive(Serialize)]
+struct ExportJson<'a> {
+    wallet: &'a str,
+    secrets: &'a Exported,
+}
+
+/// The secrets of a wallet, written to a file or kept to be shown: `{"file": "<path>"}`, or
+/// `{"text": "<seed phrase, passphrase and keys>"}`.
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Exported {
+    File(PathBuf),
+    Text(#[serde(serialize_with = "serialize_secret")] Zeroizing<String>),
+}
+
+fn serialize_secret<S: Serializer>(
+    secret: &Zeroizing<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(secret)
+}
+
+impl Exported {
+    fn print(&self, wallet: &Wallet) {
+        match self {
+            Exported::File(path) => {
+                println!("Secrets of {} written to {}", wallet.name(), path.display())
+            }
+            Exported::Text(text) => print!("{}", text.as_str()),
+        }
+    }
+}
+
+/// Exports the secrets of a new or restored wallet if `--export-secrets` asks for it.
+fn export_new(
+    wallet: &Wallet,
+    export: &ExportArgs,
+) -> Result<Option<Exported>, Box<dyn error::Error>> {
+    export
+        .export_secrets
+        .as_ref()
+        .map(|file| {
+            export_secrets(
+                wallet,
+                Some(file.as_path()).filter(|file| *file != Path::new("-")),
+                export.yes,
+            )
+        })
+        .transpose()
+}
+
+/// Exports the secrets of `wallet` to the new file `file`, or to be shown, once confirmed.
+fn export_secrets(
+    wallet: &Wallet,
+    file: Option<&Path>,
+    yes: bool,
+) -> Result<Exported, Box<dyn error::Error>> {
+    let destination = file.map_or("the terminal".to_string(), |file| {
+        file.display().to_string()
+    });
+    confirm(
+        &mut Terminal,
+        &format!(
+            "Export the seed phrase and private keys of {} to {destination}?",
+            wallet.name()
+        ),
+        yes,
+    )?;
+    match file {
+        Some(file) => {
+            wallet.export_secrets_to(file)?;
+            Ok(Exported::File(file.to_path_buf()))
+        }
+        None => Ok(Exported::Text(wallet.export_secrets()?)),
+    }
+}
+
+/// Loads a saved wallet, with its password read from `password`.
+fn load(
+    keystore: &Keystore,
+    name: &str,
+    password: &SecretSource,
+) -> Result<Wallet, Box<dyn error::Error>> {
+    let password = read_password(&mut Terminal, password)?;
+    Ok(Wallet::load_from(keystore, name, &password)?)
+}
+
+/// Saves the wallet when `--password` is given, and returns the directory of the keystore.
+fn save<'a>(
+    keystore: &'a Keystore,
+    wallet: &Wallet,
+    password: &Option<SecretSource>,
+) -> Result<Option<&'a Path>, Box<dyn error::Error>> {
+    match password {
+        Some(source) => {
+            let password = read_secret(&mut Terminal, source, "password")?;
+            wallet.save(keystore, &password)?;
+            Ok(Some(keystore.dir()))
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED_PHRASE: &str =
+        "brisk fossil brisk dream dry sing lottery mountain sound void refuse pumpkin";
+
+    fn restored() -> Wallet {
+        Wallet::restore_from_seed(
+            "main",
+            Net::Test,
+            "en",
+            SEED_PHRASE,
+            "",
+            &Derivation::default(),
+        )
+        .unwrap()
+    }
+
+    fn address(wallet: &Wallet, blockchain: Blockchain) -> Address {
+        wallet
+            .derive_address(blockchain, AddressIndex::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn show() {
+        let wallet = restored();
+        let index = json!({ "account": 0, "change": false, "index": 0 });
+        assert_eq!(
+            wallet_json(&wallet).unwrap(),
+            json!({
+                "name": "main",
+                "net": "test",
+                "watch_only": false,
+                "addresses": Blockchain::all_variants()
+                    .iter()
+                    .map(|blockchain| json!({
+                        "blockchain": blockchain,
+                        "index": index,
+                        "address": address(&wallet, *blockchain),
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        );
+    }
+
+    #[test]
+    fn new_and_restore() {
+        let wallet = restored();
+        let keystore = Path::new("/home/user/.local/share/crypto-wallet/keystore");
+        let file = Exported::File(PathBuf::from("main-secrets.txt"));
+        let json = NewWalletJson {
+            wallet: wallet_json(&wallet).unwrap(),
+            keystore: Some(keystore),
+            secrets: Some(&file),
+        };
+        assert_eq!(
+            serde_json::to_value(json).unwrap(),
+            json!({
+                "wallet": wallet_json(&wallet).unwrap(),
+                "keystore": keystore,
+                "secrets": { "file": "main-secrets.txt" },
+            })
+        );
+
+        let text = Exported::Text(wallet.export_secrets().unwrap());
+        let json = NewWalletJson {
+            wallet: wallet_json(&wallet).unwrap(),
+            keystore: None,
+            secrets: Some(&text),
+        };
+        assert_eq!(
+            serde_json::to_value(json).unwrap(),
+            json!({
+                "wallet": wallet_json(&wallet).unwrap(),
+                "keystore": null,
+                "secrets": { "text": wallet.export_secrets().unwrap().as_str() },
+            })
+        );
+
+        let json = NewWalletJson {
+            wallet: wallet_json(&wallet).unwrap(),
+            keystore: None,
+            secrets: None,
+        };
+        assert_eq!(
+            serde_json::to_value(json).unwrap(),
+            json!({ "wallet": wallet_json(&wallet).unwrap(), "keystore": null, "secrets": null })
+        );
+
+        let json = ExportJson {
+            wallet: wallet.name(),
+            secrets: &file,
+        };
+        assert_eq!(
+            serde_json::to_value(json).unwrap(),
+            json!({ "wallet": "main", "secrets": { "file": "main-secrets.txt" } })
+        );
+    }
+
+    #[test]
+    fn balance() {
+        let balance = Amount::native(Blockchain::Solana, 1_500_000_000u64);
+        assert_eq!(
+            balance_json(
+                Blockchain::Solana,
+                "9we6kjtbcZ2vy3GSLLsZTEhbAqXPTRvEyoxa8wxSqKp5",
+                &balance
+            ),
+            json!({
+                "blockchain": "solana",
+                "address": "9we6kjtbcZ2vy3GSLLsZTEhbAqXPTRvEyoxa8wxSqKp5",
+                "balance": {
+                    "asset": { "type": "native", "blockchain": "solana" },
+                    "units": "1500000000",
+                    "decimals": 9,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn send() {
+        let to = address(&restored(), Blockchain::Bitcoin);
+        let amount = Amount::native(Blockchain::Bitcoin, 50_000u64);
+        assert_eq!(
+            sent_json(&to, &amount, "f4184fc5", None),
+            json!({
+                "to": to,
+                "amount": {
+                    "asset": { "type": "native", "blockchain": "bitcoin" },
+                    "units": "50000",
+                    "decimals": 8,
+                },
+                "id": "f4184fc5",
+            })
+        );
+
+        let to = address(&restored(), Blockchain::Solana);
+        let amount = Amount::native(Blockchain::Solana, 100_000_000u64);
+        assert_eq!(
+            sent_json(&to, &amount, "5VERv8NM", Some(Commitment::Finalized)),
+            json!({
+                "to": to,
+                "amount": {
+                    "asset": { "type": "native", "blockchain": "solana" },
+                    "units": "100000000",
+                    "decimals": 9,
+                },
+                "id": "5VERv8NM",
+                "commitment": "finalized",
+            })
+        );
+    }
+
+    #[test]
+    fn history() {
+        let transactions = [
+            TransactionSummary {
+                id: "5VERv8NM".to_string(),
+                timestamp: Some(1_700_000_000),
+                direction: Direction::Outgoing,
+                amount: Amount::native(Blockchain::Solana, 100_000_000u64),
+                fee: Amount::native(Blockchain::Solana, 5000u64),
+                status: TransactionStatus::Confirmed,
+                counterparty: Some("9we6kjtbcZ2vy3GSLLsZTEhbAqXPTRvEyoxa8wxSqKp5".to_string()),
+            },
+            TransactionSummary {
+                id: "2nBhEBYY".to_string(),
+                timestamp: None,
+                direction: Direction::SelfTransfer,
+                amount: Amount::native(Blockchain::Solana, 0u64),
+                fee: Amount::native(Blockchain::Solana, 5000u64),
+                status: TransactionStatus::Pending,
+                counterparty: None,
+            },
+        ];
+        let sol = |units: &str| {
+            json!({
+                "asset": { "type": "native", "blockchain": "solana" },
+                "units": units,
+                "decimals": 9,
+            })
+        };
+        assert_eq!(
+            history_json(&transactions, Some("2nBhEBYY")),
+            json!({
+                "transactions": [
+                    {
+                        "id": "5VERv8NM",
+                        "timestamp": 1_700_000_000,
+                        "direction": "outgoing",
+                        "amount": sol("100000000"),
+                        "fee": sol("5000"),
+                        "status": "confirmed",
+                        "counterparty": "9we6kjtbcZ2vy3GSLLsZTEhbAqXPTRvEyoxa8wxSqKp5",
+                    },
+                    {
+                        "id": "2nBhEBYY",
+                        "timestamp": null,
+                        "direction": "self_transfer",
+                        "amount": sol("0"),
+                        "fee": sol("5000"),
+                        "status": "pending",
+                        "counterparty": null,
+                    },
+                ],
+                "before": "2nBhEBYY",
+            })
+        );
+        assert_eq!(
+            history_json(&[], None),
+            json!({ "transactions": [], "before": null })
+        );
+    }
+
+    #[test]
+    fn usage_errors() {
+        let error = match Cli::try_parse_from(["wallet-cli", "--output", "json", "bogus"]) {
+            Err(error) => error,
+            Ok(_) => panic!("parsed an unknown command"),
+        };
+        let json = error_json(&error);
+        assert_eq!(json["category"], "usage");
+        assert_eq!(json["code"], 2);
+        assert_eq!(json["kind"], "usage");
+        assert!(json["message"].as_str().unwrap().contains("bogus"));
+    }
+}
@@ -0,0 +1,204 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::ffi::OsString;
+use std::io;
+use std::io::Write;
+use std::process::ExitCode;
+use types::error::WalletError;
+
+/// How the CLI prints the results and errors of a command.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(super) enum Format {
+    /// Text for people.
+    #[default]
+    Text,
+    /// One JSON document on stdout, and errors as JSON on stderr.
+    Json,
+}
+
+impl Format {
+    /// The format asked for by `--output` in raw command line arguments, for the errors of
+    /// command lines that can't be parsed.
+    pub(super) fn from_args(args: impl IntoIterator<Item = OsString>) -> Self {
+        let args = args.into_iter().collect::<Vec<_>>();
+        let json = args.iter().any(|arg| arg == "--output=json")
+            || args
+                .windows(2)
+                .any(|pair| pair[0] == "--output" && pair[1] == "json");
+        if json {
+            Format::Json
+        } else {
+            Format::Text
+        }
+    }
+
+    /// Prints the result of a command: `json` as one line of JSON, or `text` for people. The
+    /// JSON is written straight to stdout, so that secrets in it aren't copied.
+    pub(super) fn print(self, json: impl Serialize, text: impl FnOnce()) {
+        match self {
+            Format::Text => text(),
+            Format::Json => {
+                let mut stdout = io::stdout().lock();
+                serde_json::to_writer(&mut stdout, &json)
+                    .map_err(io::Error::from)
+                    .and_then(|()| writeln!(stdout))
+                    .expect("failed printing to stdout");
+            }
+        }
+    }
+
+    /// Prints the error that ended a command on stderr and returns the exit code of its
+    /// category.
+    pub(super) fn fail(self, error: &(dyn Error + 'static)) -> ExitCode {
+        match self {
+            Format::Text => eprintln!("Error: {error}"),
+            Format::Json => eprintln!("{}", json!({ "error": error_json(error) })),
+        }
+        ExitCode::from(ErrorCategory::of(error) as u8)
+    }
+}
+
+/// What went wrong, and the exit code of the CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum ErrorCategory {
+    Other = 1,
+    /// A command line that can't be parsed.
+    Usage = 2,
+    /// An invalid address, amount, key, seed phrase or other argument, a wrong network, or an
+    /// unreadable secret.
+    Input = 3,
+    /// A missing or watch-only wallet, a wrong password or an unreadable keystore.
+    Wallet = 4,
+    /// A node or API server that failed, timed out or couldn't be reached.
+    Network = 5,
+    /// Insufficient funds, or a transaction that was refused or not confirmed.
+    Transaction = 6,
+}
+
+impl ErrorCategory {
+    pub(super) fn of(error: &(dyn Error + 'static)) -> Self {
+        match error.downcast_ref::<WalletError>() {
+            Some(
+                WalletError::InvalidMnemonic(_)
+                | WalletError::UnsupportedBlockchain(_)
+                | WalletError::WrongNetwork(_)
+                | WalletError::InvalidInput(_)
+                | WalletError::InvalidAddress(_)
+                | WalletError::InvalidKey(_)
+                | WalletError::InvalidAmount(_),
+            ) => ErrorCategory::Input,
+            Some(
+                WalletError::WatchOnly { .. }
+                | WalletError::WalletNotFound(_)
+                | WalletError::WalletExists(_)
+                | WalletError::WrongPassword
+                | WalletError::Storage(_),
+            ) => ErrorCategory::Wallet,
+            Some(WalletError::Rpc { .. } | WalletError::Network(_) | WalletError::Timeout(_)) => {
+                ErrorCategory::Network
+            }
+            Some(WalletError::InsufficientFunds { .. } | WalletError::Transaction(_)) => {
+                ErrorCategory::Transaction
+            }
+            Some(WalletError::Crypto(_)) => ErrorCategory::Other,
+            // Secrets that couldn't be read or whose export wasn't confirmed.
+            None if error.is::<io::Error>() => ErrorCategory::Input,
+            None if error.is::<clap::Error>() => ErrorCategory::Usage,
+            None => ErrorCategory::Other,
+        }
+    }
+}
+
+/// `{"category": "network", "code": 5, "kind": "rpc", "message": "RPC error -32000: nonce too
+/// low", "details": {"code": -32000, "message": "nonce too low"}}`, where `kind` and `details`
+/// are those of the serialized [`WalletError`], or `kind` is the category for other errors.
+pub(super) fn error_json(error: &(dyn Error + 'static)) -> Value {
+    let category = ErrorCategory::of(error);
+    let mut json = json!({
+        "category": category,
+        "code": category as u8,
+        "kind": category,
+        "message": error.to_string(),
+    });
+    if let Some(Value::Object(fields)) = error
+        .downcast_ref::<WalletError>()
+        .and_then(|error| serde_json::to_value(error).ok())
+    {
+        json.as_object_mut().unwrap().extend(fields);
+    }
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::amount::Amount;
+    use types::shared::Blockchain;
+
+    #[test]
+    fn wallet_errors() {
+        let error = WalletError::Rpc {
+            code: -32000,
+            message: "nonce too low".to_string(),
+        };
+        assert_eq!(
+            error_json(&error),
+            json!({
+                "category": "network",
+                "code": 5,
+                "kind": "rpc",
+                "message": "RPC error -32000: nonce too low",
+                "details": {"code": -32000, "message": "nonce too low"},
+            })
+        );
+        assert_eq!(
+            error_json(&WalletError::WrongPassword),
+            json!({
+                "category": "wallet",
+                "code": 4,
+                "kind": "wrong_password",
+                "message": "Wrong password",
+            })
+        );
+        assert_eq!(
+            ErrorCategory::of(&WalletError::insufficient_funds(
+                Amount::native(Blockchain::Bitcoin, 2),
+                Amount::native(Blockchain::Bitcoin, 1),
+            )),
+            ErrorCategory::Transaction
+        );
+        assert_eq!(
+            ErrorCategory::of(&WalletError::InvalidAmount("-1".to_string())),
+            ErrorCategory::Input
+        );
+    }
+
+    #[test]
+    fn output_flag() {
+        let format = |args: &[&str]| Format::from_args(args.iter().map(OsString::from));
+        assert_eq!(
+            format(&["wallet-cli", "--output", "json", "show"]),
+            Format::Json
+        );
+        assert_eq!(
+            format(&["wallet-cli", "list", "--output=json"]),
+            Format::Json
+        );
+        assert_eq!(format(&["wallet-cli", "--output", "text"]), Format::Text);
+        assert_eq!(format(&["wallet-cli", "json"]), Format::Text);
+    }
+
+    #[test]
+    fn other_errors() {
+        let error = io::Error::other("Cancelled");
+        assert_eq!(
+            error_json(&error),
+            json!({"category": "input", "code": 3, "kind": "input", "message": "Cancelled"})
+        );
+        let error: Box<dyn Error> = "No balance".into();
+        assert_eq!(ErrorCategory::of(error.as_ref()), ErrorCategory::Other);
+    }
+}
@@ -0,0 +1,343 @@
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+/// Where a seed phrase or passphrase is read from, so that it never appears in the arguments of
+/// the command, which end up in the shell history and the process list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum SecretSource {
+    /// A hidden prompt on the terminal, answered twice.
+    Prompt,
+    /// The next line of the standard input, so that a script can pipe several secrets.
+    Stdin,
+    /// The content of an open file descriptor, e.g. `3<secret.txt`.
+    Fd(u32),
+    /// An environment variable.
+    Env(String),
+}
+
+impl FromStr for SecretSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "prompt" => Ok(SecretSource::Prompt),
+            None if s == "stdin" => Ok(SecretSource::Stdin),
+            Some(("fd", fd)) => fd
+                .parse()
+                .map(SecretSource::Fd)
+                .map_err(|_| format!("Invalid file descriptor {fd}")),
+            Some(("env", name)) if !name.is_empty() => Ok(SecretSource::Env(name.to_string())),
+            _ => Err(format!(
+                "Unknown secret source {s}, expected prompt, stdin, fd:<n> or env:<VAR>"
+            )),
+        }
+    }
+}
+
+impl Display for SecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SecretSource::Prompt => write!(f, "prompt"),
+            SecretSource::Stdin => write!(f, "stdin"),
+            SecretSource::Fd(fd) => write!(f, "fd:{fd}"),
+            SecretSource::Env(name) => write!(f, "env:{name}"),
+        }
+    }
+}
+
+/// The interactive input of the CLI, which tests replace with scripted answers.
+pub(super) trait Console {
+    /// Reads a line from the terminal without echoing it.
+    fn read_hidden(&mut self, prompt: &str) -> io::Result<String>;
+    /// Reads the next line of the standard input, `None` at its end.
+    fn read_stdin(&mut self) -> io::Result<Option<String>>;
+    fn var(&self, name: &str) -> Option<String>;
+    /// Asks a yes/no question on the terminal, `false` unless answered yes.
+    fn confirm(&mut self, question: &str) -> io::Result<bool>;
+}
+
+/// The terminal and standard input of the process.
+pub(super) struct Terminal;
+
+/// The controlling terminal, which confirmations are read from.
+#[cfg(unix)]
+const TTY: &str = "/dev/tty";
+#[cfg(windows)]
+const TTY: &str = "CONIN$";
+
+impl Console for Terminal {
+    fn read_hidden(&mut self, prompt: &str) -> io::Result<String> {
+        rpassword::prompt_password(prompt)
+    }
+
+    fn read_stdin(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line)? {
+            0 => Ok(None),
+            _ => Ok(Some(line)),
+        }
+    }
+
+    fn var(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+
+    /// Reads the answer from the terminal rather than the standard input, whose next line
+    /// may be a piped secret.
+    fn confirm(&mut self, question: &str) -> io::Result<bool> {
+        let tty = fs::File::open(TTY).map_err(|e| {
+            io::Error::new(
+                ErrorKind::NotConnected,
+                format!("No terminal to confirm on ({e}), use --yes instead"),
+            )
+        })?;
+        eprint!("{question} [y/N] ");
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        io::BufReader::new(tty).read_line(&mut answer)?;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+}
+
+/// Reads the secret called `name`, e.g. "seed phrase", from `source`, without its line ending.
+/// The prompt asks for it twice.
+pub(super) fn read_secret(
+    console: &mut impl Console,
+    source: &SecretSource,
+    name: &str,
+) -> io::Result<Zeroizing<String>> {
+    read(console, source, name, true)
+}
+
+/// Reads the password of a saved wallet from `source`, asked once by the prompt.
+pub(super) fn read_password(
+    console: &mut impl Console,
+    source: &SecretSource,
+) -> io::Result<Zeroizing<String>> {
+    read(console, source, "password", false)
+}
+
+fn read(
+    console: &mut impl Console,
+    source: &SecretSource,
+    name: &str,
+    repeat: bool,
+) -> io::Result<Zeroizing<String>> {
+    let secret = match source {
+        SecretSource::Prompt => std::mem::take(&mut *prompt(console, name, repeat)?),
+        SecretSource::Stdin => console.read_stdin()?.ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("No {name} on the standard input"),
+            )
+        })?,
+        SecretSource::Fd(fd) => fs::read_to_string(format!("/dev/fd/{fd}")).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Can't read the {name} from file descriptor {fd}: {e}"),
+            )
+        })?,
+        SecretSource::Env(var) => console
+            .var(var)
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("{var} is not set")))?,
+    };
+    // Truncated in place, so that the line ending is zeroized with the rest of the buffer.
+    let mut secret = Zeroizing::new(secret);
+    let len = secret.trim_end_matches(['\r', '\n']).len();
+    secret.truncate(len);
+    Ok(secret)
+}
+
+fn prompt(console: &mut impl Console, name: &str, repeat: bool) -> io::Result<Zeroizing<String>> {
+    let secret = console
+        .read_hidden(&format!("Enter {name}: "))
+        .map_err(|e| io::Error::new(e.kind(), format!("Can't prompt for the {name}: {e}")))?;
+    let secret = Zeroizing::new(secret);
+    if repeat && Zeroizing::new(console.read_hidden(&format!("Repeat {name}: "))?) != secret {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("The {name} doesn't match its confirmation"),
+        ));
+    }
+    Ok(secret)
+}
+
+/// Asks `question` before a secret is shown or written, unless `yes` already confirms it.
+pub(super) fn confirm(console: &mut impl Console, question: &str, yes: bool) -> io::Result<()> {
+    if yes || console.confirm(question)? {
+        Ok(())
+    } else {
+        Err(io::Error::other("Cancelled"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, VecDeque};
+
+    #[derive(Default)]
+    struct ScriptedConsole {
+        answers: VecDeque<&'static str>,
+        stdin: VecDeque<&'static str>,
+        env: HashMap<&'static str, &'static str>,
+        confirmations: VecDeque<bool>,
+        prompts: Vec<String>,
+    }
+
+    impl Console for ScriptedConsole {
+        fn read_hidden(&mut self, prompt: &str) -> io::Result<String> {
+            self.prompts.push(prompt.to_string());
+            self.answers
+                .pop_front()
+                .map(str::to_string)
+                .ok_or_else(|| io::Error::new(ErrorKind::NotConnected, "no terminal"))
+        }
+
+        fn read_stdin(&mut self) -> io::Result<Option<String>> {
+            Ok(self.stdin.pop_front().map(str::to_string))
+        }
+
+        fn var(&self, name: &str) -> Option<String> {
+            self.env.get(name).map(|value| value.to_string())
+        }
+
+        fn confirm(&mut self, question: &str) -> io::Result<bool> {
+            self.prompts.push(question.to_string());
+            Ok(self.confirmations.pop_front().unwrap_or(false))
+        }
+    }
+
+    #[test]
+    fn sources() {
+        for source in ["prompt", "stdin", "fd:3", "env:WALLET_SEED_PHRASE"] {
+            assert_eq!(source.parse::<SecretSource>().unwrap().to_string(), source);
+        }
+        for invalid in ["", "tty", "fd:-1", "fd:", "env:"] {
+            assert!(invalid.parse::<SecretSource>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn prompt_with_confirmation() {
+        let mut console = ScriptedConsole {
+            answers: VecDeque::from(["abandon ability", "abandon ability"]),
+            ..ScriptedConsole::default()
+        };
+        let secret = read_secret(&mut console, &SecretSource::Prompt, "seed phrase").unwrap();
+        assert_eq!(secret.as_str(), "abandon ability");
+        assert_eq!(
+            console.prompts,
+            ["Enter seed phrase: ", "Repeat seed phrase: "]
+        );
+
+        let mut console = ScriptedConsole {
+            answers: VecDeque::from(["correct", "c0rrect"]),
+            ..ScriptedConsole::default()
+        };
+        let error = read_secret(&mut console, &SecretSource::Prompt, "passphrase").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+        // Without a terminal.
+        let error = read_secret(
+            &mut ScriptedConsole::default(),
+            &SecretSource::Prompt,
+            "passphrase",
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotConnected);
+    }
+
+    #[test]
+    fn password_prompt() {
+        let mut console = ScriptedConsole {
+            answers: VecDeque::from(["hunter2"]),
+            ..ScriptedConsole::default()
+        };
+        assert_eq!(
+            read_password(&mut console, &SecretSource::Prompt)
+                .unwrap()
+                .as_str(),
+            "hunter2"
+        );
+        assert_eq!(console.prompts, ["Enter password: "]);
+    }
+
+    #[test]
+    fn stdin_lines() {
+        let mut console = ScriptedConsole {
+            stdin: VecDeque::from(["abandon ability\n", " pass phrase \r\n"]),
+            ..ScriptedConsole::default()
+        };
+        assert_eq!(
+            read_secret(&mut console, &SecretSource::Stdin, "seed phrase")
+                .unwrap()
+                .as_str(),
+            "abandon ability"
+        );
+        // Only the line ending is removed.
+        assert_eq!(
+            read_secret(&mut console, &SecretSource::Stdin, "passphrase")
+                .unwrap()
+                .as_str(),
+            " pass phrase "
+        );
+        let error = read_secret(&mut console, &SecretSource::Stdin, "passphrase").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        assert!(console.prompts.is_empty());
+    }
+
+    #[test]
+    fn env() {
+        let mut console = ScriptedConsole {
+            env: HashMap::from([("WALLET_PASSPHRASE", "secret")]),
+            ..ScriptedConsole::default()
+        };
+        let source = SecretSource::Env("WALLET_PASSPHRASE".to_string());
+        assert_eq!(
+            read_secret(&mut console, &source, "passphrase")
+                .unwrap()
+                .as_str(),
+            "secret"
+        );
+        let source = SecretSource::Env("WALLET_SEED_PHRASE".to_string());
+        let error = read_secret(&mut console, &source, "seed phrase").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_descriptor() {
+        use std::os::fd::AsRawFd;
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), "abandon ability\n").unwrap();
+        let fd = fs::File::open(file.path()).unwrap();
+        let source = SecretSource::Fd(fd.as_raw_fd() as u32);
+        assert_eq!(
+            read_secret(&mut ScriptedConsole::default(), &source, "seed phrase")
+                .unwrap()
+                .as_str(),
+            "abandon ability"
+        );
+    }
+
+    #[test]
+    fn confirmation() {
+        let mut console = ScriptedConsole {
+            confirmations: VecDeque::from([true, false]),
+            ..ScriptedConsole::default()
+        };
+        assert!(confirm(&mut console, "Export?", false).is_ok());
+        assert!(confirm(&mut console, "Export?", false).is_err());
+        // No answer counts as no.
+        assert!(confirm(&mut console, "Export?", false).is_err());
+        assert_eq!(console.prompts.len(), 3);
+
+        assert!(confirm(&mut console, "Export?", true).is_ok());
+        assert_eq!(console.prompts.len(), 3);
+    }
+}
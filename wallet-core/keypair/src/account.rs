@@ -1,16 +1,56 @@
-use crate::bitcoin::BitcoinKeypair;
+use crate::bitcoin::{BitcoinAccountXpub, BitcoinKeypair};
+use crate::ethereum::EthereumKeypair;
 use crate::solana::SolanaKeypair;
-use crate::Keypair;
+use crate::{write_secrets, Derivation, Keypair};
+use bitcoin::bip32::KeySource;
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1;
 use mnemonic::{Mnemonic, WordCount};
-use std::collections::HashMap;
-use std::error::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
-use std::fmt::{Display, Formatter};
+use std::fmt::{Display, Formatter, Write};
+use types::error::WalletError;
 use types::shared::{Address, Blockchain, Net};
+use zeroize::Zeroizing;
+
+/// Position of a key in the HD tree of a blockchain: `m/purpose'/coin'/account'/change/index`.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct AddressIndex {
+    pub account: u32,
+    pub change: bool,
+    pub index: u32,
+}
+
+impl AddressIndex {
+    pub fn new(account: u32, change: bool, index: u32) -> Self {
+        Self {
+            account,
+            change,
+            index,
+        }
+    }
+}
+
+impl Display for AddressIndex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "account {}, {} {}",
+            self.account,
+            if self.change { "change" } else { "receive" },
+            self.index
+        )
+    }
+}
 
 pub struct Account {
     net: Net,
     mnemonic: Mnemonic,
+    seed: Zeroizing<Vec<u8>>,
+    derivation: Derivation,
     addresses: Addresses,
     keys: Keys,
 }
@@ -21,66 +61,225 @@ impl Account {
         language_code: &str,
         word_count: i32,
         passphrase: &str,
-    ) -> Result<Self, Box<dyn Error>> {
+        derivation: &Derivation,
+    ) -> Result<Self, WalletError> {
         let mnemonic = Mnemonic::new(language_code, WordCount::from(word_count));
-        Account::build(net, mnemonic, passphrase)
+        Account::build(net, mnemonic, passphrase, derivation)
     }
     pub fn restore_from_seed(
         net: Net,
         language_code: &str,
         seed_phrase: &str,
         passphrase: &str,
-    ) -> Result<Self, Box<dyn Error>> {
+        derivation: &Derivation,
+    ) -> Result<Self, WalletError> {
         let mnemonic = Mnemonic::new_from_phrase(language_code, seed_phrase)?;
-        Account::build(net, mnemonic, passphrase)
-    }
-
-    fn build(net: Net, mnemonic: Mnemonic, passphrase: &str) -> Result<Self, Box<dyn Error>> {
-        let seed = mnemonic.seed(passphrase);
-        let key_pairs: HashMap<Blockchain, KeypairType> = HashMap::from([
-            (
-                Blockchain::Bitcoin,
-                KeypairType::Bitcoin(BitcoinKeypair::new(net, &seed)?),
-            ),
-            (
-                Blockchain::Solana,
-                KeypairType::Solana(SolanaKeypair::new(net, &seed)?),
-            ),
-        ]);
-
-        Ok(Self {
+        Account::build(net, mnemonic, passphrase, derivation)
+    }
+
+    pub fn net(&self) -> Net {
+        self.net
+    }
+
+    pub fn seed_phrase(&self) -> &str {
+        self.mnemonic.seed_phrase()
+    }
+
+    /// The seed phrase and the private keys of the opened addresses, as text to back up.
+    pub fn export_secrets(&self) -> Zeroizing<String> {
+        write_secrets(|f| self.write_secrets(f))
+    }
+
+    /// Writes [`Account::export_secrets`] to `f`, to be part of a larger export.
+    pub fn write_secrets(&self, f: &mut dyn Write) -> fmt::Result {
+        write!(
+            f,
+            "Network: {}\n\nSeed phrase:\n{}\n\nKeys:\n{}",
+            self.net,
+            self.seed_phrase(),
+            self.keys
+        )
+    }
+
+    pub fn derivation(&self) -> &Derivation {
+        &self.derivation
+    }
+
+    /// Derives the address at `index` without remembering it.
+    pub fn derive_address(
+        &self,
+        blockchain: Blockchain,
+        index: AddressIndex,
+    ) -> Result<Address, WalletError> {
+        self.derive(blockchain, index)?.address()
+    }
+
+    /// Lists the first `count` receive addresses of an account, followed by its first `count`
+    /// change addresses on blockchains that use them.
+    pub fn list_addresses(
+        &self,
+        blockchain: Blockchain,
+        account: u32,
+        count: u32,
+    ) -> Result<Vec<(AddressIndex, Address)>, WalletError> {
+        address_indexes(blockchain, account, count)
+            .map(|index| Ok((index, self.derive_address(blockchain, index)?)))
+            .collect()
+    }
+
+    /// Derives the address at `index` and adds it, with its key, to the opened addresses.
+    pub fn open(
+        &mut self,
+        blockchain: Blockchain,
+        index: AddressIndex,
+    ) -> Result<Address, WalletError> {
+        let keypair = self.derive(blockchain, index)?;
+        let address = keypair.address()?;
+        self.addresses
+            .0
+            .insert((blockchain, index), address.clone());
+        self.keys.0.insert((blockchain, index), keypair.pk()?);
+        Ok(address)
+    }
+
+    /// Derives the secret key of the address at `index`, to sign its transactions.
+    pub fn secret_key(
+        &self,
+        blockchain: Blockchain,
+        index: AddressIndex,
+    ) -> Result<Zeroizing<Vec<u8>>, WalletError> {
+        Ok(self.derive(blockchain, index)?.secret_key())
+    }
+
+    /// The account-level extended public key of Bitcoin account `account`.
+    pub fn bitcoin_xpub(&self, account: u32) -> Result<BitcoinAccountXpub, WalletError> {
+        BitcoinAccountXpub::new(self.net, &self.seed, self.derivation.bitcoin, account)
+    }
+
+    /// The public key of the Bitcoin address at `index` and its BIP-32 origin, for PSBTs.
+    pub fn bitcoin_key_origin(
+        &self,
+        index: AddressIndex,
+    ) -> Result<(secp256k1::PublicKey, KeySource), WalletError> {
+        crate::bitcoin::key_origin(self.net, &self.seed, self.derivation.bitcoin, index)
+    }
+
+    /// Signs and finalizes the inputs of a PSBT that spend this account's P2WPKH outputs.
+    pub fn sign_bitcoin_psbt(&self, psbt: &mut Psbt) -> Result<(), WalletError> {
+        crate::bitcoin::sign_psbt(self.net, &self.seed, psbt)
+    }
+
+    /// The opened address indexes, ordered by blockchain and index.
+    pub fn opened(&self) -> Vec<(Blockchain, AddressIndex)> {
+        self.addresses.0.keys().copied().collect()
+    }
+
+    /// The opened address at `index`.
+    pub fn address(&self, blockchain: Blockchain, index: AddressIndex) -> Option<&Address> {
+        self.addresses.0.get(&(blockchain, index))
+    }
+
+    fn build(
+        net: Net,
+        mnemonic: Mnemonic,
+        passphrase: &str,
+        derivation: &Derivation,
+    ) -> Result<Self, WalletError> {
+        let mut account = Self {
             net,
+            seed: mnemonic.seed(passphrase),
             mnemonic,
-            addresses: Addresses::new(&key_pairs)?,
-            keys: Keys::new(&key_pairs)?,
+            derivation: derivation.clone(),
+            addresses: Addresses(BTreeMap::new()),
+            keys: Keys(BTreeMap::new()),
+        };
+        Blockchain::all_variants()
+            .iter()
+            .try_for_each(|&blockchain| {
+                account
+                    .open(blockchain, AddressIndex::default())
+                    .map(|_| ())
+            })?;
+        Ok(account)
+    }
+
+    fn derive(
+        &self,
+        blockchain: Blockchain,
+        index: AddressIndex,
+    ) -> Result<KeypairType, WalletError> {
+        Ok(match blockchain {
+            Blockchain::Bitcoin => KeypairType::Bitcoin(BitcoinKeypair::new(
+                self.net,
+                &self.seed,
+                self.derivation.bitcoin,
+                index,
+            )?),
+            Blockchain::Ethereum => {
+                KeypairType::Ethereum(EthereumKeypair::new(self.net, &self.seed, index)?)
+            }
+            Blockchain::Solana => KeypairType::Solana(SolanaKeypair::new(
+                self.net,
+                &self.seed,
+                &self.derivation.solana,
+                index,
+            )?),
         })
     }
 }
 
+/// The first `count` receive indexes of an account, followed by its first `count` change
+/// indexes. Solana wallets have no separate chain of change addresses.
+pub(crate) fn address_indexes(
+    blockchain: Blockchain,
+    account: u32,
+    count: u32,
+) -> impl Iterator<Item = AddressIndex> {
+    let chains: &[bool] = match blockchain {
+        Blockchain::Solana => &[false],
+        _ => &[false, true],
+    };
+    chains
+        .iter()
+        .flat_map(move |&change| (0..count).map(move |i| AddressIndex::new(account, change, i)))
+}
+
 enum KeypairType {
     Bitcoin(BitcoinKeypair),
+    Ethereum(EthereumKeypair),
     Solana(SolanaKeypair),
 }
 
 impl Keypair for KeypairType {
-    fn address(&self) -> Result<Address, Box<dyn Error>> {
+    fn address(&self) -> Result<Address, WalletError> {
         match self {
             KeypairType::Bitcoin(keypair) => keypair.address(),
+            KeypairType::Ethereum(keypair) => keypair.address(),
             KeypairType::Solana(keypair) => keypair.address(),
         }
     }
 
-    fn pk(&self) -> Result<String, Box<dyn Error>> {
+    fn pk(&self) -> Result<Zeroizing<String>, WalletError> {
         match self {
             KeypairType::Bitcoin(keypair) => keypair.pk(),
+            KeypairType::Ethereum(keypair) => keypair.pk(),
             KeypairType::Solana(keypair) => keypair.pk(),
         }
     }
+
+    fn secret_key(&self) -> Zeroizing<Vec<u8>> {
+        match self {
+            KeypairType::Bitcoin(keypair) => keypair.secret_key(),
+            KeypairType::Ethereum(keypair) => keypair.secret_key(),
+            KeypairType::Solana(keypair) => keypair.secret_key(),
+        }
+    }
 }
 
+/// The public view of the account: its network and opened addresses, never its secrets.
 impl Display for Account {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let divider = "=".repeat(self.mnemonic.len());
+        let divider = "=".repeat(DIVIDER_WIDTH);
 
         // Write opening divider
         writeln!(f, "{}", divider)?;
@@ -92,62 +291,127 @@ impl Display for Account {
         writeln!(f, "Addresses:")?;
         write!(f, "{}", self.addresses)?;
 
-        // Write mnemonic section
-        writeln!(f, "{}", self.mnemonic)?;
-
-        // Write keys section
-        writeln!(f, "Keys:")?;
-        write!(f, "{}", self.keys)?;
-
         // Write closing divider
         writeln!(f, "{}", divider)
     }
 }
 
-struct Keys(HashMap<Blockchain, String>);
+const DIVIDER_WIDTH: usize = 80;
 
-impl Keys {
-    fn new(key_pairs: &HashMap<Blockchain, KeypairType>) -> Result<Self, Box<dyn Error>> {
-        Ok(Self(
-            key_pairs
-                .iter()
-                .map(|(&k, v)| v.pk().map(|pk| (k, pk)))
-                .collect::<Result<_, _>>()?,
-        ))
+/// The keys of the opened addresses, as WIF, hex or base58 strings that are zeroized on drop.
+struct Keys(BTreeMap<(Blockchain, AddressIndex), Zeroizing<String>>);
+
+impl Display for Keys {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0
+            .iter()
+            .try_for_each(|((k, i), v)| writeln!(f, "{} [{}]: {}", k, i, v.as_str()))
     }
 }
 
-impl Display for Keys {
+struct Addresses(BTreeMap<(Blockchain, AddressIndex), Address>);
+
+impl Display for Addresses {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut sorted = self.0.iter().collect::<Vec<_>>();
-        sorted.sort_by_key(|(k, _)| *k);
-        sorted
+        self.0
             .iter()
-            .try_for_each(|(k, v)| writeln!(f, "{}: {}", k, v))?;
-        Ok(())
+            .try_for_each(|((k, i), v)| writeln!(f, "{} [{}]: {}", k, i, v))
     }
 }
 
-struct Addresses(HashMap<Blockchain, Address>);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn account() -> Account {
+        Account::restore_from_seed(Net::Main, "en", PHRASE, "", &Derivation::default()).unwrap()
+    }
+
+    #[test]
+    fn opens_first_address_of_each_blockchain() {
+        let account = account();
+        assert_eq!(
+            account.opened(),
+            vec![
+                (Blockchain::Bitcoin, AddressIndex::default()),
+                (Blockchain::Ethereum, AddressIndex::default()),
+                (Blockchain::Solana, AddressIndex::default()),
+            ]
+        );
+        assert_eq!(
+            account
+                .address(Blockchain::Solana, AddressIndex::default())
+                .unwrap(),
+            "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+        );
+    }
 
-impl Addresses {
-    fn new(key_pairs: &HashMap<Blockchain, KeypairType>) -> Result<Self, Box<dyn Error>> {
-        Ok(Self(
-            key_pairs
+    #[test]
+    fn open() {
+        let mut account = account();
+        let index = AddressIndex::new(1, false, 0);
+        assert_eq!(
+            account.open(Blockchain::Solana, index).unwrap(),
+            "Hh8QwFUA6MtVu1qAoq12ucvFHNwCcVTV7hpWjeY1Hztb"
+        );
+        assert!(account.opened().contains(&(Blockchain::Solana, index)));
+        assert!(account.to_string().contains(
+            "Solana [account 1, receive 0]: Hh8QwFUA6MtVu1qAoq12ucvFHNwCcVTV7hpWjeY1Hztb"
+        ));
+    }
+
+    #[test]
+    fn list_addresses() {
+        let account = account();
+        let bitcoin = account.list_addresses(Blockchain::Bitcoin, 0, 2).unwrap();
+        assert_eq!(bitcoin.len(), 4);
+        assert_eq!(
+            bitcoin[..3]
                 .iter()
-                .map(|(&k, v)| v.address().map(|adr| (k, adr)))
-                .collect::<Result<_, _>>()?,
-        ))
+                .map(|(i, a)| (*i, a.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    AddressIndex::new(0, false, 0),
+                    "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+                ),
+                (
+                    AddressIndex::new(0, false, 1),
+                    "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g"
+                ),
+                (
+                    AddressIndex::new(0, true, 0),
+                    "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el"
+                ),
+            ]
+        );
+        assert_eq!(
+            account
+                .list_addresses(Blockchain::Solana, 0, 3)
+                .unwrap()
+                .len(),
+            3
+        );
     }
-}
 
-impl Display for Addresses {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut sorted = self.0.iter().collect::<Vec<_>>();
-        sorted.sort_by_key(|(k, _)| *k);
-        sorted
-            .iter()
-            .try_for_each(|(k, v)| writeln!(f, "{}: {}", k, v))?;
-        Ok(())
+    #[test]
+    fn display_has_no_secrets() {
+        let account = account();
+        let public = account.to_string();
+        assert!(public.contains(
+            "Bitcoin [account 0, receive 0]: bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        ));
+        assert!(!public.contains("abandon"));
+        assert!(!public.contains("Keys"));
+
+        let secrets = account.export_secrets();
+        assert_eq!(secrets.capacity(), secrets.len());
+        assert!(secrets.contains(&format!("Seed phrase:\n{PHRASE}\n")));
+        // The WIF of the first BIP-84 key of the test vector.
+        assert!(secrets.contains(
+            "Bitcoin [account 0, receive 0]: KyZpNDKnfs94vbrwhJneDi77V6jF64PWPF8x5cdJb8ifgg2DUc9d"
+        ));
     }
 }
@@ -1,53 +1,210 @@
+use crate::account::AddressIndex;
 use crate::{write_as_base58, Keypair};
-use bitcoin::bip32::{DerivationPath, Xpriv};
+use bitcoin::bip32::{ChildNumber, DerivationPath, Fingerprint, KeySource, Xpriv, Xpub};
 use bitcoin::hashes::{sha256, Hash};
-use bitcoin::{secp256k1, CompressedPublicKey, Network};
+use bitcoin::psbt::Psbt;
+use bitcoin::{secp256k1, CompressedPublicKey, Network, Witness};
 use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
 use std::str::FromStr;
+use types::error::WalletError;
 use types::shared::{Address, Net};
+use zeroize::Zeroizing;
+
+/// The script type of the derived addresses, which also selects the BIP-44 purpose.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum BitcoinAddressType {
+    /// Legacy pay-to-pubkey-hash addresses (BIP-44).
+    P2pkh,
+    /// SegWit nested in pay-to-script-hash (BIP-49).
+    P2shP2wpkh,
+    /// Native SegWit v0 (BIP-84).
+    #[default]
+    P2wpkh,
+    /// Taproot single key (BIP-86).
+    P2tr,
+}
+
+impl BitcoinAddressType {
+    pub fn all_variants() -> &'static [BitcoinAddressType] {
+        &[
+            BitcoinAddressType::P2pkh,
+            BitcoinAddressType::P2shP2wpkh,
+            BitcoinAddressType::P2wpkh,
+            BitcoinAddressType::P2tr,
+        ]
+    }
+
+    pub fn purpose(&self) -> u32 {
+        match self {
+            BitcoinAddressType::P2pkh => 44,
+            BitcoinAddressType::P2shP2wpkh => 49,
+            BitcoinAddressType::P2wpkh => 84,
+            BitcoinAddressType::P2tr => 86,
+        }
+    }
+}
+
+impl Display for BitcoinAddressType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                BitcoinAddressType::P2pkh => "p2pkh",
+                BitcoinAddressType::P2shP2wpkh => "p2sh-p2wpkh",
+                BitcoinAddressType::P2wpkh => "p2wpkh",
+                BitcoinAddressType::P2tr => "p2tr",
+            }
+        )
+    }
+}
 
 #[derive(Debug)]
-pub(super) struct BitcoinKeypair(Network, secp256k1::SecretKey, secp256k1::PublicKey);
+pub struct ParseBitcoinAddressTypeError;
+
+impl Display for ParseBitcoinAddressTypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid Bitcoin address type, expected p2pkh, p2sh-p2wpkh, p2wpkh or p2tr"
+        )
+    }
+}
+
+impl Error for ParseBitcoinAddressTypeError {}
+
+impl FromStr for BitcoinAddressType {
+    type Err = ParseBitcoinAddressTypeError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        BitcoinAddressType::all_variants()
+            .iter()
+            .find(|t| t.to_string().eq_ignore_ascii_case(input))
+            .copied()
+            .ok_or(ParseBitcoinAddressTypeError)
+    }
+}
+
+pub(super) struct BitcoinKeypair {
+    network: Network,
+    address_type: BitcoinAddressType,
+    secret_key: secp256k1::SecretKey,
+    public_key: secp256k1::PublicKey,
+}
 
 impl Keypair for BitcoinKeypair {
-    fn address(&self) -> Result<Address, Box<dyn Error>> {
+    fn address(&self) -> Result<Address, WalletError> {
         Ok(self.address())
     }
 
-    fn pk(&self) -> Result<String, Box<dyn Error>> {
-        Ok(self.secret_key_to_wif(true))
+    fn pk(&self) -> Result<Zeroizing<String>, WalletError> {
+        Ok(Zeroizing::new(self.secret_key_to_wif(true)))
+    }
+
+    fn secret_key(&self) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(self.secret_key.secret_bytes().to_vec())
+    }
+}
+
+impl fmt::Debug for BitcoinKeypair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BitcoinKeypair")
+            .field("network", &self.network)
+            .field("address_type", &self.address_type)
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An extended private key, erased on drop like the keypairs derived from it.
+pub(crate) struct SecretXpriv(Xpriv);
+
+impl SecretXpriv {
+    pub(crate) fn master(network: Network, seed: &[u8]) -> Result<Self, WalletError> {
+        Xpriv::new_master(network, seed)
+            .map(SecretXpriv)
+            .map_err(WalletError::crypto)
+    }
+
+    pub(crate) fn derive<C: secp256k1::Signing>(
+        &self,
+        secp: &secp256k1::Secp256k1<C>,
+        path: &DerivationPath,
+    ) -> Result<Self, WalletError> {
+        self.0
+            .derive_priv(secp, path)
+            .map(SecretXpriv)
+            .map_err(WalletError::crypto)
+    }
+}
+
+impl Deref for SecretXpriv {
+    type Target = Xpriv;
+
+    fn deref(&self) -> &Xpriv {
+        &self.0
+    }
+}
+
+impl Drop for SecretXpriv {
+    fn drop(&mut self) {
+        self.0.private_key.non_secure_erase();
+    }
+}
+
+impl Drop for BitcoinKeypair {
+    fn drop(&mut self) {
+        self.secret_key.non_secure_erase();
     }
 }
 
 impl BitcoinKeypair {
-    pub fn new(net: Net, seed: &[u8]) -> Result<Self, Box<dyn Error>> {
-        Self::from_seed(convert(net), seed)
+    pub fn new(
+        net: Net,
+        seed: &[u8],
+        address_type: BitcoinAddressType,
+        index: AddressIndex,
+    ) -> Result<Self, WalletError> {
+        Self::from_seed(Network::from(net), seed, address_type, index)
     }
 
-    fn from_seed(network: Network, seed: &[u8]) -> Result<Self, Box<dyn Error>> {
+    fn from_seed(
+        network: Network,
+        seed: &[u8],
+        address_type: BitcoinAddressType,
+        index: AddressIndex,
+    ) -> Result<Self, WalletError> {
         let secp = bitcoin::secp256k1::Secp256k1::new();
-        let master_key = Xpriv::new_master(network, seed).unwrap();
-        let derivation_path = DerivationPath::from_str("m/84'/0'/0'/0/0").unwrap();
-        let child_key = master_key.derive_priv(&secp, &derivation_path).unwrap();
-        let private_key = child_key.private_key;
-        Ok(Self(network, private_key, private_key.public_key(&secp)))
+        let derivation_path =
+            account_path(network, address_type, index.account)?.extend(address_path(index)?);
+        let master_key = SecretXpriv::master(network, seed)?;
+        let private_key = master_key.derive(&secp, &derivation_path)?.private_key;
+        Ok(Self {
+            network,
+            address_type,
+            secret_key: private_key,
+            public_key: private_key.public_key(&secp),
+        })
     }
 
     fn address(&self) -> Address {
-        bitcoin::address::Address::p2wpkh(&CompressedPublicKey(self.2), self.0).to_string()
+        address(self.network, self.address_type, self.public_key)
     }
 
     fn secret_key_to_wif(&self, is_compressed: bool) -> String {
         // Step 1: Get the raw private key bytes
-        let key_bytes = self.1.secret_bytes();
+        let key_bytes = Zeroizing::new(self.secret_key.secret_bytes());
 
         // Step 2: Add the network prefix
-        let mut extended_key = vec![];
-        match self.0 {
+        let mut extended_key = Zeroizing::new(Vec::with_capacity(38));
+        match self.network {
             Network::Bitcoin => extended_key.push(0x80), // Mainnet prefix
             _ => extended_key.push(0xEF),                // Testnet prefix
         }
-        extended_key.extend(&key_bytes);
+        extended_key.extend(key_bytes.iter());
 
         // Step 3: Add compression flag (optional)
         if is_compressed {
@@ -60,14 +217,646 @@ impl BitcoinKeypair {
         extended_key.extend(&checksum[0..4]);
 
         // Step 5: Encode to Base58
-        write_as_base58(extended_key)
+        write_as_base58(&extended_key)
+    }
+}
+
+/// The account-level extended public key of a Bitcoin account, `m/purpose'/coin'/account'`.
+///
+/// It lets watch-only software derive every receive and change address of the account.
+pub struct BitcoinAccountXpub {
+    network: Network,
+    address_type: BitcoinAddressType,
+    /// The master key fingerprint and the derivation path of the key, when known.
+    origin: Option<KeySource>,
+    xpub: Xpub,
+}
+
+impl BitcoinAccountXpub {
+    pub(crate) fn new(
+        net: Net,
+        seed: &[u8],
+        address_type: BitcoinAddressType,
+        account: u32,
+    ) -> Result<Self, WalletError> {
+        let network = Network::from(net);
+        let secp = secp256k1::Secp256k1::new();
+        let master_key = SecretXpriv::master(network, seed)?;
+        let path = account_path(network, address_type, account)?;
+        let account_key = master_key.derive(&secp, &path)?;
+        Ok(Self {
+            network,
+            address_type,
+            origin: Some((master_key.fingerprint(&secp), path)),
+            xpub: Xpub::from_priv(&secp, &account_key),
+        })
+    }
+
+    /// Parses an extended public key in any SLIP-132 encoding, or an output descriptor of
+    /// the receive or change addresses of an account.
+    ///
+    /// The script type comes from the descriptor or the SLIP-132 prefix. Plain xpub/tpub keys
+    /// don't tell, so `address_type` is used for them, P2PKH if it is `None`.
+    pub(crate) fn parse(
+        net: Net,
+        input: &str,
+        address_type: Option<BitcoinAddressType>,
+    ) -> Result<Self, WalletError> {
+        let input = input.trim();
+        let (key, origin, descriptor_type) = match parse_descriptor(input)? {
+            Some((descriptor_type, key)) => {
+                let (origin, key) = parse_key_expression(key)?;
+                (key, origin, Some(descriptor_type))
+            }
+            None => (input, None, None),
+        };
+
+        let mut data = bitcoin::base58::decode_check(key).map_err(WalletError::invalid_key)?;
+        if data.len() != 78 {
+            return Err(WalletError::invalid_key(
+                "Invalid extended public key length",
+            ));
+        }
+        let (mainnet, key_type, _) = SLIP132_VERSIONS
+            .iter()
+            .find(|(_, _, version)| data[..4] == version[..])
+            .copied()
+            .ok_or_else(|| WalletError::invalid_key("Unknown extended public key version"))?;
+        if mainnet != (net == Net::Main) {
+            return Err(WalletError::InvalidKey(format!(
+                "The extended public key is not a {} key",
+                net
+            )));
+        }
+        let address_type = match (descriptor_type.or(key_type), address_type) {
+            (Some(found), Some(expected)) if found != expected => {
+                return Err(WalletError::InvalidKey(format!(
+                    "The key is for {} addresses, not {}",
+                    found, expected
+                )))
+            }
+            (found, expected) => found.or(expected).unwrap_or(BitcoinAddressType::P2pkh),
+        };
+        data[..4].copy_from_slice(&slip132_version(mainnet, None));
+
+        Ok(Self {
+            network: Network::from(net),
+            address_type,
+            origin,
+            xpub: Xpub::decode(&data).map_err(WalletError::invalid_key)?,
+        })
+    }
+
+    pub fn address_type(&self) -> BitcoinAddressType {
+        self.address_type
+    }
+
+    pub fn path(&self) -> Option<String> {
+        self.origin.as_ref().map(|(_, path)| format!("m/{}", path))
+    }
+
+    /// The fingerprint of the master key, as used in key origins.
+    pub fn fingerprint(&self) -> Option<String> {
+        self.origin
+            .as_ref()
+            .map(|(fingerprint, _)| fingerprint.to_string())
+    }
+
+    /// The extended public key with the SLIP-132 version bytes of its script type
+    /// (xpub/ypub/zpub on mainnet, tpub/upub/vpub on test networks).
+    pub fn slip132(&self) -> String {
+        let key_type = match self.address_type {
+            BitcoinAddressType::P2shP2wpkh | BitcoinAddressType::P2wpkh => Some(self.address_type),
+            BitcoinAddressType::P2pkh | BitcoinAddressType::P2tr => None,
+        };
+        let mut data = self.xpub.encode();
+        data[..4].copy_from_slice(&slip132_version(self.network == Network::Bitcoin, key_type));
+        bitcoin::base58::encode_check(&data)
+    }
+
+    /// The BIP-380 output descriptor, with checksum, of the receive or change addresses.
+    pub fn descriptor(&self, change: bool) -> String {
+        let origin = self
+            .origin
+            .as_ref()
+            .map(|(fingerprint, path)| format!("[{}/{}]", fingerprint, path))
+            .unwrap_or_default();
+        let key = format!("{}{}/{}/*", origin, self.xpub, change as u32);
+        let descriptor = match self.address_type {
+            BitcoinAddressType::P2pkh => format!("pkh({key})"),
+            BitcoinAddressType::P2shP2wpkh => format!("sh(wpkh({key}))"),
+            BitcoinAddressType::P2wpkh => format!("wpkh({key})"),
+            BitcoinAddressType::P2tr => format!("tr({key})"),
+        };
+        format!("{}#{}", descriptor, descriptor_checksum(&descriptor))
     }
+
+    /// Derives the address at `index` from the public key alone.
+    pub(crate) fn derive_address(&self, index: AddressIndex) -> Result<Address, WalletError> {
+        if self.xpub.child_number != hardened(index.account)? {
+            return Err(WalletError::InvalidKey(format!(
+                "The extended public key is not the key of account {}",
+                index.account
+            )));
+        }
+        let key = self
+            .xpub
+            .derive_pub(
+                &secp256k1::Secp256k1::verification_only(),
+                &address_path(index)?,
+            )
+            .map_err(WalletError::crypto)?;
+        Ok(address(self.network, self.address_type, key.public_key))
+    }
+}
+
+/// The public key of the address at `index` and its BIP-32 origin, as recorded in PSBTs.
+pub(crate) fn key_origin(
+    net: Net,
+    seed: &[u8],
+    address_type: BitcoinAddressType,
+    index: AddressIndex,
+) -> Result<(secp256k1::PublicKey, KeySource), WalletError> {
+    let network = Network::from(net);
+    let secp = secp256k1::Secp256k1::new();
+    let master_key = SecretXpriv::master(network, seed)?;
+    let path = account_path(network, address_type, index.account)?.extend(address_path(index)?);
+    let public_key = master_key
+        .derive(&secp, &path)?
+        .private_key
+        .public_key(&secp);
+    Ok((public_key, (master_key.fingerprint(&secp), path)))
+}
+
+/// Signs the inputs of `psbt` whose BIP-32 origin is a key of `seed`, then finalizes them.
+///
+/// Only P2WPKH inputs are supported; the PSBT is left unchanged if any input can't be
+/// finalized.
+pub(crate) fn sign_psbt(net: Net, seed: &[u8], psbt: &mut Psbt) -> Result<(), WalletError> {
+    let secp = secp256k1::Secp256k1::new();
+    let master_key = SecretXpriv::master(Network::from(net), seed)?;
+    let mut signed = psbt.clone();
+    signed.sign(&*master_key, &secp).map_err(|(_, errors)| {
+        WalletError::Crypto(
+            errors
+                .iter()
+                .map(|(input, e)| format!("Input {input}: {e}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    })?;
+    for (i, input) in signed.inputs.iter_mut().enumerate() {
+        let is_p2wpkh = input
+            .witness_utxo
+            .as_ref()
+            .is_some_and(|utxo| utxo.script_pubkey.is_p2wpkh());
+        let (public_key, signature) = match input.partial_sigs.first_key_value() {
+            Some((public_key, signature)) if is_p2wpkh && input.partial_sigs.len() == 1 => {
+                (*public_key, *signature)
+            }
+            _ => {
+                return Err(WalletError::Transaction(format!(
+                    "Input {i} is not a P2WPKH input of this wallet"
+                )))
+            }
+        };
+        // As a BIP-174 finalizer, keep only the UTXO and the final witness.
+        *input = bitcoin::psbt::Input {
+            witness_utxo: input.witness_utxo.take(),
+            final_script_witness: Some(Witness::p2wpkh(&signature, &public_key.inner)),
+            ..Default::default()
+        };
+    }
+    *psbt = signed;
+    Ok(())
+}
+
+/// SLIP-132 extended public key versions: mainnet, script type (`None` for the plain
+/// xpub/tpub shared by the other types) and version bytes.
+const SLIP132_VERSIONS: [(bool, Option<BitcoinAddressType>, [u8; 4]); 6] = [
+    (true, None, [0x04, 0x88, 0xb2, 0x1e]),
+    (
+        true,
+        Some(BitcoinAddressType::P2shP2wpkh),
+        [0x04, 0x9d, 0x7c, 0xb2],
+    ),
+    (
+        true,
+        Some(BitcoinAddressType::P2wpkh),
+        [0x04, 0xb2, 0x47, 0x46],
+    ),
+    (false, None, [0x04, 0x35, 0x87, 0xcf]),
+    (
+        false,
+        Some(BitcoinAddressType::P2shP2wpkh),
+        [0x04, 0x4a, 0x52, 0x62],
+    ),
+    (
+        false,
+        Some(BitcoinAddressType::P2wpkh),
+        [0x04, 0x5f, 0x1c, 0xf6],
+    ),
+];
+
+fn slip132_version(mainnet: bool, key_type: Option<BitcoinAddressType>) -> [u8; 4] {
+    SLIP132_VERSIONS
+        .iter()
+        .find(|&&(m, t, _)| m == mainnet && t == key_type)
+        .map(|&(_, _, version)| version)
+        // The table has an entry for every combination.
+        .unwrap()
+}
+
+/// Splits a single-key descriptor into its script type and key expression, after checking
+/// its checksum. Returns `None` if `input` is not a descriptor.
+fn parse_descriptor(input: &str) -> Result<Option<(BitcoinAddressType, &str)>, WalletError> {
+    if !input.contains('(') {
+        return Ok(None);
+    }
+    let descriptor = match input.split_once('#') {
+        Some((descriptor, checksum)) => {
+            if descriptor_checksum(descriptor) != checksum {
+                return Err(WalletError::invalid_key("Invalid descriptor checksum"));
+            }
+            descriptor
+        }
+        None => input,
+    };
+    [
+        ("pkh(", ")", BitcoinAddressType::P2pkh),
+        ("sh(wpkh(", "))", BitcoinAddressType::P2shP2wpkh),
+        ("wpkh(", ")", BitcoinAddressType::P2wpkh),
+        ("tr(", ")", BitcoinAddressType::P2tr),
+    ]
+    .iter()
+    .find_map(|&(prefix, suffix, address_type)| {
+        descriptor
+            .strip_prefix(prefix)
+            .and_then(|d| d.strip_suffix(suffix))
+            .map(|key| Some((address_type, key)))
+    })
+    .ok_or_else(|| {
+        WalletError::invalid_key("Unsupported descriptor, expected pkh, sh(wpkh), wpkh or tr")
+    })
+}
+
+/// Splits a descriptor key expression, `[fingerprint/path]key/change/*`, into its origin and
+/// key. Only the receive (`/0/*`), change (`/1/*`) or both (`/<0;1>/*`) chains are supported.
+fn parse_key_expression(input: &str) -> Result<(Option<KeySource>, &str), WalletError> {
+    let (origin, input) = match input.strip_prefix('[') {
+        Some(rest) => {
+            let (origin, key) = rest
+                .split_once(']')
+                .ok_or_else(|| WalletError::invalid_key("Unterminated key origin"))?;
+            let (fingerprint, path) = origin.split_once('/').unwrap_or((origin, ""));
+            let path = DerivationPath::from_str(format!("m/{}", path).trim_end_matches('/'))
+                .map_err(WalletError::invalid_key)?;
+            let fingerprint =
+                Fingerprint::from_str(fingerprint).map_err(WalletError::invalid_key)?;
+            (Some((fingerprint, path)), key)
+        }
+        None => (None, input),
+    };
+    let key = ["/0/*", "/1/*", "/<0;1>/*", ""]
+        .iter()
+        .find_map(|suffix| input.strip_suffix(suffix))
+        .filter(|key| !key.contains('/'))
+        .ok_or_else(|| {
+            WalletError::invalid_key("Only the /0/*, /1/* and /<0;1>/* derivations are supported")
+        })?;
+    Ok((origin, key))
+}
+
+/// Computes the 8-character descriptor checksum defined in BIP-380.
+fn descriptor_checksum(descriptor: &str) -> String {
+    const INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+    const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    const GENERATOR: [u64; 5] = [
+        0xf5dee51989,
+        0xa9fdca3312,
+        0x1bab10e32d,
+        0x3706b1677a,
+        0x644d626ffd,
+    ];
+
+    fn polymod(checksum: u64, value: u64) -> u64 {
+        let top = checksum >> 35;
+        let mut checksum = ((checksum & 0x7ffffffff) << 5) ^ value;
+        (0..5)
+            .filter(|i| (top >> i) & 1 == 1)
+            .for_each(|i| checksum ^= GENERATOR[i]);
+        checksum
+    }
+
+    let mut checksum = 1;
+    let mut groups = vec![];
+    for c in descriptor.chars() {
+        // Descriptors only contain characters of the input charset.
+        let position = INPUT_CHARSET.find(c).unwrap_or_default() as u64;
+        checksum = polymod(checksum, position & 31);
+        groups.push(position >> 5);
+        if groups.len() == 3 {
+            checksum = polymod(checksum, groups[0] * 9 + groups[1] * 3 + groups[2]);
+            groups.clear();
+        }
+    }
+    match groups.as_slice() {
+        [a] => checksum = polymod(checksum, *a),
+        [a, b] => checksum = polymod(checksum, a * 3 + b),
+        _ => {}
+    }
+    (0..8).for_each(|_| checksum = polymod(checksum, 0));
+    checksum ^= 1;
+    (0..8)
+        .map(|i| CHECKSUM_CHARSET[((checksum >> (5 * (7 - i))) & 31) as usize] as char)
+        .collect()
+}
+
+fn address(
+    network: Network,
+    address_type: BitcoinAddressType,
+    public_key: secp256k1::PublicKey,
+) -> Address {
+    let compressed = CompressedPublicKey(public_key);
+    match address_type {
+        BitcoinAddressType::P2pkh => bitcoin::address::Address::p2pkh(compressed, network),
+        BitcoinAddressType::P2shP2wpkh => bitcoin::address::Address::p2shwpkh(&compressed, network),
+        BitcoinAddressType::P2wpkh => bitcoin::address::Address::p2wpkh(&compressed, network),
+        BitcoinAddressType::P2tr => bitcoin::address::Address::p2tr(
+            &secp256k1::Secp256k1::verification_only(),
+            public_key.x_only_public_key().0,
+            None,
+            network,
+        ),
+    }
+    .to_string()
+}
+
+/// `m/purpose'/coin'/account'`.
+fn account_path(
+    network: Network,
+    address_type: BitcoinAddressType,
+    account: u32,
+) -> Result<DerivationPath, WalletError> {
+    Ok(DerivationPath::from(vec![
+        hardened(address_type.purpose())?,
+        hardened(coin_type(network))?,
+        hardened(account)?,
+    ]))
 }
 
-fn convert(net: Net) -> Network {
-    match net {
-        Net::Main => Network::Bitcoin,
-        Net::Test => Network::Testnet,
-        Net::Dev | Net::Local => Network::Regtest,
+/// `change/index` below the account key.
+fn address_path(index: AddressIndex) -> Result<[ChildNumber; 2], WalletError> {
+    let normal = |i| ChildNumber::from_normal_idx(i).map_err(WalletError::invalid_key);
+    Ok([normal(index.change as u32)?, normal(index.index)?])
+}
+
+fn hardened(index: u32) -> Result<ChildNumber, WalletError> {
+    ChildNumber::from_hardened_idx(index).map_err(WalletError::invalid_key)
+}
+
+/// BIP-44 coin type: 0' for Bitcoin, 1' for all test networks.
+fn coin_type(network: Network) -> u32 {
+    match network {
+        Network::Bitcoin => 0,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mnemonic::Mnemonic;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn address(net: Net, address_type: BitcoinAddressType, index: AddressIndex) -> Address {
+        let seed = Mnemonic::new_from_phrase("en", PHRASE).unwrap().seed("");
+        BitcoinKeypair::new(net, &seed, address_type, index)
+            .unwrap()
+            .address()
+    }
+
+    #[test]
+    fn mainnet_addresses() {
+        [
+            (
+                BitcoinAddressType::P2pkh,
+                "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA",
+            ),
+            (
+                BitcoinAddressType::P2shP2wpkh,
+                "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf",
+            ),
+            (
+                BitcoinAddressType::P2wpkh,
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            ),
+            (
+                BitcoinAddressType::P2tr,
+                "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            ),
+        ]
+        .iter()
+        .for_each(|&(address_type, expected)| {
+            assert_eq!(
+                address(Net::Main, address_type, AddressIndex::default()),
+                expected
+            )
+        });
+    }
+
+    #[test]
+    fn testnet_uses_coin_type_1() {
+        assert_eq!(
+            address(
+                Net::Test,
+                BitcoinAddressType::P2shP2wpkh,
+                AddressIndex::default()
+            ),
+            "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2"
+        );
+        assert_eq!(
+            address(
+                Net::Test,
+                BitcoinAddressType::P2wpkh,
+                AddressIndex::default()
+            ),
+            "tb1q6rz28mcfaxtmd6v789l9rrlrusdprr9pqcpvkl"
+        );
+    }
+
+    fn xpub(net: Net, address_type: BitcoinAddressType) -> BitcoinAccountXpub {
+        let seed = Mnemonic::new_from_phrase("en", PHRASE).unwrap().seed("");
+        BitcoinAccountXpub::new(net, &seed, address_type, 0).unwrap()
+    }
+
+    #[test]
+    fn slip132() {
+        [
+            (
+                BitcoinAddressType::P2shP2wpkh,
+                "ypub6Ww3ibxVfGzLrAH1PNcjyAWenMTbbAosGNB6VvmSEgytSER9azLDWCxoJwW7Ke7icmizBMXrzBx9979FfaHxHcrArf3zbeJJJUZPf663zsP",
+            ),
+            (
+                BitcoinAddressType::P2wpkh,
+                "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs",
+            ),
+            (
+                BitcoinAddressType::P2tr,
+                "xpub6BgBgsespWvERF3LHQu6CnqdvfEvtMcQjYrcRzx53QJjSxarj2afYWcLteoGVky7D3UKDP9QyrLprQ3VCECoY49yfdDEHGCtMMj92pReUsQ",
+            ),
+        ]
+        .iter()
+        .for_each(|&(address_type, expected)| {
+            assert_eq!(xpub(Net::Main, address_type).slip132(), expected)
+        });
+        assert!(xpub(Net::Test, BitcoinAddressType::P2wpkh)
+            .slip132()
+            .starts_with("vpub"));
+        assert!(xpub(Net::Test, BitcoinAddressType::P2pkh)
+            .slip132()
+            .starts_with("tpub"));
+    }
+
+    #[test]
+    fn descriptor() {
+        let xpub = xpub(Net::Main, BitcoinAddressType::P2wpkh);
+        assert_eq!(xpub.path().unwrap(), "m/84'/0'/0'");
+        assert_eq!(xpub.fingerprint().unwrap(), "73c5da0a");
+        assert_eq!(
+            xpub.descriptor(false),
+            "wpkh([73c5da0a/84'/0'/0']xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V/0/*)#wc3n3van"
+        );
+        assert_eq!(
+            xpub.descriptor(true),
+            "wpkh([73c5da0a/84'/0'/0']xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V/1/*)#lv5jvedt"
+        );
+    }
+
+    #[test]
+    fn parse_extended_public_key() {
+        let zpub = xpub(Net::Main, BitcoinAddressType::P2wpkh).slip132();
+        let parsed = BitcoinAccountXpub::parse(Net::Main, &zpub, None).unwrap();
+        assert_eq!(parsed.address_type(), BitcoinAddressType::P2wpkh);
+        assert_eq!(parsed.fingerprint(), None);
+        assert_eq!(
+            parsed.derive_address(AddressIndex::default()).unwrap(),
+            address(
+                Net::Main,
+                BitcoinAddressType::P2wpkh,
+                AddressIndex::default()
+            )
+        );
+        assert_eq!(
+            parsed
+                .derive_address(AddressIndex::new(0, true, 3))
+                .unwrap(),
+            address(
+                Net::Main,
+                BitcoinAddressType::P2wpkh,
+                AddressIndex::new(0, true, 3)
+            )
+        );
+        assert!(parsed
+            .derive_address(AddressIndex::new(1, false, 0))
+            .is_err());
+        assert!(BitcoinAccountXpub::parse(Net::Test, &zpub, None).is_err());
+        assert!(
+            BitcoinAccountXpub::parse(Net::Main, &zpub, Some(BitcoinAddressType::P2tr)).is_err()
+        );
+
+        let tr = xpub(Net::Main, BitcoinAddressType::P2tr).slip132();
+        let parsed = BitcoinAccountXpub::parse(Net::Main, &tr, Some(BitcoinAddressType::P2tr));
+        assert_eq!(
+            parsed
+                .unwrap()
+                .derive_address(AddressIndex::default())
+                .unwrap(),
+            address(Net::Main, BitcoinAddressType::P2tr, AddressIndex::default())
+        );
+
+        let vpub = xpub(Net::Test, BitcoinAddressType::P2wpkh).slip132();
+        assert_eq!(
+            BitcoinAccountXpub::parse(Net::Test, &vpub, None)
+                .unwrap()
+                .derive_address(AddressIndex::default())
+                .unwrap(),
+            address(
+                Net::Test,
+                BitcoinAddressType::P2wpkh,
+                AddressIndex::default()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_descriptor() {
+        let xpub = xpub(Net::Main, BitcoinAddressType::P2shP2wpkh);
+        let parsed = BitcoinAccountXpub::parse(Net::Main, &xpub.descriptor(true), None).unwrap();
+        assert_eq!(parsed.address_type(), BitcoinAddressType::P2shP2wpkh);
+        assert_eq!(parsed.path(), xpub.path());
+        assert_eq!(parsed.fingerprint(), xpub.fingerprint());
+        assert_eq!(parsed.descriptor(false), xpub.descriptor(false));
+        assert_eq!(
+            parsed.derive_address(AddressIndex::default()).unwrap(),
+            address(
+                Net::Main,
+                BitcoinAddressType::P2shP2wpkh,
+                AddressIndex::default()
+            )
+        );
+
+        let descriptor = xpub.descriptor(false);
+        let (without_checksum, _) = descriptor.split_once('#').unwrap();
+        assert!(BitcoinAccountXpub::parse(Net::Main, without_checksum, None).is_ok());
+        assert!(BitcoinAccountXpub::parse(
+            Net::Main,
+            &format!("{without_checksum}#qqqqqqqq"),
+            None
+        )
+        .is_err());
+        assert!(BitcoinAccountXpub::parse(
+            Net::Main,
+            &without_checksum.replace("/0/*", "/0/1"),
+            None
+        )
+        .is_err());
+        assert!(BitcoinAccountXpub::parse(
+            Net::Main,
+            &without_checksum.replace("sh(", "wsh("),
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn bip380_checksum() {
+        assert_eq!(descriptor_checksum("raw(deadbeef)"), "89f8spxm");
+    }
+
+    #[test]
+    fn parse_address_type() {
+        BitcoinAddressType::all_variants()
+            .iter()
+            .for_each(|t| assert_eq!(t.to_string().parse::<BitcoinAddressType>().unwrap(), *t));
+        assert!("p2sh".parse::<BitcoinAddressType>().is_err());
+    }
+
+    #[test]
+    fn debug_is_redacted() {
+        let seed = Mnemonic::new_from_phrase("en", PHRASE).unwrap().seed("");
+        let keypair = BitcoinKeypair::new(
+            Net::Main,
+            &seed,
+            BitcoinAddressType::P2wpkh,
+            AddressIndex::default(),
+        )
+        .unwrap();
+        let debug = format!("{keypair:?}");
+        assert!(debug.starts_with("BitcoinKeypair { network: Bitcoin, address_type: P2wpkh"));
+        assert!(debug.ends_with(", .. }"));
+        assert!(!debug.contains(keypair.pk().unwrap().as_str()));
+        assert!(!debug.contains(&keypair.secret_key.display_secret().to_string()));
     }
 }
@@ -0,0 +1,157 @@
+use crate::account::AddressIndex;
+use crate::bitcoin::SecretXpriv;
+use crate::Keypair;
+use bitcoin::bip32::DerivationPath;
+use bitcoin::hex::DisplayHex;
+use bitcoin::{secp256k1, Network};
+use sha3::{Digest, Keccak256};
+use std::fmt;
+use std::str::FromStr;
+use types::error::WalletError;
+use types::shared::{Address, Net};
+use zeroize::Zeroizing;
+
+pub(super) struct EthereumKeypair(secp256k1::SecretKey, secp256k1::PublicKey);
+
+impl Keypair for EthereumKeypair {
+    fn address(&self) -> Result<Address, WalletError> {
+        Ok(self.address())
+    }
+
+    fn pk(&self) -> Result<Zeroizing<String>, WalletError> {
+        Ok(Zeroizing::new(self.pk()))
+    }
+
+    fn secret_key(&self) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(self.0.secret_bytes().to_vec())
+    }
+}
+
+impl fmt::Debug for EthereumKeypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EthereumKeypair")
+            .field("address", &self.address())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for EthereumKeypair {
+    fn drop(&mut self) {
+        self.0.non_secure_erase();
+    }
+}
+
+impl EthereumKeypair {
+    pub fn new(_: Net, seed: &[u8], index: AddressIndex) -> Result<Self, WalletError> {
+        Self::from_seed(seed, index)
+    }
+
+    fn from_seed(seed: &[u8], index: AddressIndex) -> Result<Self, WalletError> {
+        let secp = secp256k1::Secp256k1::new();
+        // The network only affects the xpriv serialization, not the derived keys.
+        let master_key = SecretXpriv::master(Network::Bitcoin, seed)?;
+        let derivation_path = DerivationPath::from_str(&format!(
+            "m/44'/60'/{}'/{}/{}",
+            index.account, index.change as u32, index.index
+        ))
+        .map_err(WalletError::invalid_key)?;
+        let private_key = master_key.derive(&secp, &derivation_path)?.private_key;
+        Ok(Self(private_key, private_key.public_key(&secp)))
+    }
+
+    fn address(&self) -> Address {
+        // The address is the last 20 bytes of the Keccak-256 hash of the uncompressed
+        // public key without its 0x04 prefix.
+        let hash = Keccak256::digest(&self.1.serialize_uncompressed()[1..]);
+        to_checksum_address(&hash[12..])
+    }
+
+    fn pk(&self) -> String {
+        self.0.secret_bytes().to_lower_hex_string()
+    }
+}
+
+/// Encodes an address with the mixed-case checksum defined in EIP-55.
+pub fn to_checksum_address(address: &[u8]) -> Address {
+    let hex = address.to_lower_hex_string();
+    let hash = Keccak256::digest(hex.as_bytes());
+    let checksummed = hex
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+    format!("0x{checksummed}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hex::FromHex;
+    use mnemonic::Mnemonic;
+
+    fn keypair(phrase: &str) -> EthereumKeypair {
+        let seed = Mnemonic::new_from_phrase("en", phrase).unwrap().seed("");
+        EthereumKeypair::new(Net::Main, &seed, AddressIndex::default()).unwrap()
+    }
+
+    #[test]
+    fn hardhat_default_account() {
+        let keypair = keypair("test test test test test test test test test test test junk");
+        assert_eq!(
+            keypair.address(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+        assert_eq!(
+            keypair.pk(),
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        );
+    }
+
+    #[test]
+    fn abandon_about() {
+        let keypair = keypair(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        );
+        assert_eq!(
+            keypair.address(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+        assert_eq!(
+            keypair.pk(),
+            "1ab42cc412b618bdea3a599e3c9bae199ebf030895b039e9db1e30dafb12b727"
+        );
+    }
+
+    #[test]
+    fn eip55_checksum() {
+        [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ]
+        .iter()
+        .for_each(|&expected| {
+            let bytes = Vec::<u8>::from_hex(&expected[2..].to_lowercase()).unwrap();
+            assert_eq!(to_checksum_address(&bytes), expected);
+        });
+    }
+
+    #[test]
+    fn debug_is_redacted() {
+        let keypair = keypair(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        );
+        assert_eq!(
+            format!("{keypair:?}"),
+            "EthereumKeypair { address: \"0x9858EfFD232B4033E47d90003D41EC34EcaEda94\", .. }"
+        );
+    }
+}
@@ -1,21 +1,60 @@
-use std::error::Error;
+use std::fmt;
 use std::str::from_utf8;
+use types::error::WalletError;
 use types::shared::Address;
+use zeroize::Zeroizing;
 
 pub mod account;
 mod bitcoin;
+mod ethereum;
 mod solana;
+pub mod watch;
+
+pub use crate::bitcoin::{BitcoinAccountXpub, BitcoinAddressType, ParseBitcoinAddressTypeError};
+pub use crate::ethereum::to_checksum_address;
+pub use crate::solana::{ParseSolanaDerivationError, SolanaDerivation};
+
+/// How keys are derived from the seed on the blockchains that offer a choice.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Derivation {
+    pub bitcoin: BitcoinAddressType,
+    pub solana: SolanaDerivation,
+}
 
 pub(crate) trait Keypair {
-    fn address(&self) -> Result<Address, Box<dyn Error>>;
-    fn pk(&self) -> Result<String, Box<dyn Error>>;
+    fn address(&self) -> Result<Address, WalletError>;
+    fn pk(&self) -> Result<Zeroizing<String>, WalletError>;
+    /// The raw secret key, used to sign transactions.
+    fn secret_key(&self) -> Zeroizing<Vec<u8>>;
+}
+
+/// Writes a text holding secrets into a string of its exact size. The text is measured first, so
+/// that the string never grows and leaves copies of the secrets behind.
+pub fn write_secrets(write: impl Fn(&mut dyn fmt::Write) -> fmt::Result) -> Zeroizing<String> {
+    let mut len = Len(0);
+    // Neither counting nor writing to a string can fail.
+    let _ = write(&mut len);
+    let mut text = Zeroizing::new(String::with_capacity(len.0));
+    let _ = write(&mut *text);
+    text
+}
+
+/// Counts the bytes of a text without storing it.
+struct Len(usize);
+
+impl fmt::Write for Len {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
 }
 
 const MAX_BASE58_LEN: usize = 64;
 
-pub(crate) fn write_as_base58(key: Vec<u8>) -> String {
-    let mut out = [0u8; MAX_BASE58_LEN];
-    let out_slice: &mut [u8] = &mut out;
+/// Encodes a key as base58, in a buffer that is zeroized afterwards since the key can be secret.
+pub(crate) fn write_as_base58(key: &[u8]) -> String {
+    let mut out = Zeroizing::new([0u8; MAX_BASE58_LEN]);
+    let out_slice: &mut [u8] = out.as_mut_slice();
     // This will never fail because the only possible error is BufferTooSmall,
     // and we will never call it with too small a buffer.
     let len = bs58::encode(key).onto(out_slice).unwrap();
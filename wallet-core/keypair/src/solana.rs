@@ -1,41 +1,282 @@
+use crate::account::AddressIndex;
 use crate::{write_as_base58, Keypair};
+use bitcoin::bip32::{ChildNumber, DerivationPath};
 use ed25519_dalek::SecretKey;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
 use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use types::error::WalletError;
 use types::shared::{Address, Net};
+use zeroize::Zeroizing;
+
+/// How Solana keys are derived from a BIP-39 seed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum SolanaDerivation {
+    /// SLIP-0010 ed25519 derivation along `m/44'/501'/{account}'/{index}'`, as done by Phantom,
+    /// Solflare and `solana-keygen` (`prompt://?key=0/0`).
+    #[default]
+    Bip44,
+    /// SLIP-0010 ed25519 derivation along a fixed path of hardened indexes.
+    Slip10(DerivationPath),
+    /// The first 32 bytes of the seed used as the secret key, as earlier versions of the
+    /// wallet did. Kept so that funds sent to those addresses can still be recovered.
+    Legacy,
+}
+
+impl Display for SolanaDerivation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SolanaDerivation::Bip44 => write!(f, "bip44"),
+            SolanaDerivation::Slip10(path) => {
+                write!(f, "m")?;
+                path.into_iter()
+                    .try_for_each(|child| write!(f, "/{}", child))
+            }
+            SolanaDerivation::Legacy => write!(f, "legacy"),
+        }
+    }
+}
 
-/// A vanilla Ed25519 key pair.
 #[derive(Debug)]
+pub struct ParseSolanaDerivationError(String);
+
+impl Display for ParseSolanaDerivationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid Solana derivation: {}", self.0)
+    }
+}
+
+impl Error for ParseSolanaDerivationError {}
+
+impl FromStr for SolanaDerivation {
+    type Err = ParseSolanaDerivationError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if input.eq_ignore_ascii_case("bip44") {
+            return Ok(SolanaDerivation::Bip44);
+        }
+        if input.eq_ignore_ascii_case("legacy") {
+            return Ok(SolanaDerivation::Legacy);
+        }
+        let path = DerivationPath::from_str(input)
+            .map_err(|e| ParseSolanaDerivationError(e.to_string()))?;
+        if !path.as_ref().iter().all(ChildNumber::is_hardened) {
+            return Err(ParseSolanaDerivationError(
+                "ed25519 supports only hardened indexes".to_string(),
+            ));
+        }
+        Ok(SolanaDerivation::Slip10(path))
+    }
+}
+
+/// A vanilla Ed25519 key pair, whose signing key zeroizes itself on drop.
 pub(super) struct SolanaKeypair(ed25519_dalek::SigningKey);
 
 impl Keypair for SolanaKeypair {
-    fn address(&self) -> Result<Address, Box<dyn Error>> {
+    fn address(&self) -> Result<Address, WalletError> {
         Ok(self.address())
     }
 
-    fn pk(&self) -> Result<String, Box<dyn Error>> {
-        Ok(self.pk())
+    fn pk(&self) -> Result<Zeroizing<String>, WalletError> {
+        Ok(Zeroizing::new(self.pk()))
+    }
+
+    fn secret_key(&self) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(self.0.to_bytes().to_vec())
+    }
+}
+
+impl fmt::Debug for SolanaKeypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SolanaKeypair")
+            .field("address", &self.address())
+            .finish_non_exhaustive()
     }
 }
 
 impl SolanaKeypair {
-    pub fn new(_: Net, seed: &[u8]) -> Result<Self, Box<dyn Error>> {
-        Self::from_seed(seed)
+    pub fn new(
+        _: Net,
+        seed: &[u8],
+        derivation: &SolanaDerivation,
+        index: AddressIndex,
+    ) -> Result<Self, WalletError> {
+        if index.change {
+            return Err(WalletError::InvalidInput(
+                "Solana has no change addresses".to_string(),
+            ));
+        }
+        match derivation {
+            SolanaDerivation::Bip44 => Self::from_path(
+                seed,
+                &DerivationPath::from_str(&format!(
+                    "m/44'/501'/{}'/{}'",
+                    index.account, index.index
+                ))
+                .map_err(WalletError::invalid_key)?,
+            ),
+            _ if index != AddressIndex::default() => Err(WalletError::InvalidInput(format!(
+                "The {} Solana derivation has a single address",
+                derivation
+            ))),
+            SolanaDerivation::Slip10(path) => Self::from_path(seed, path),
+            SolanaDerivation::Legacy => Self::from_seed(seed),
+        }
     }
 
-    fn from_seed(seed: &[u8]) -> Result<Self, Box<dyn Error>> {
+    fn from_path(seed: &[u8], path: &DerivationPath) -> Result<Self, WalletError> {
+        let secret_key = derive_slip10(seed, path)?;
+        Ok(Self(ed25519_dalek::SigningKey::from_bytes(&secret_key)))
+    }
+
+    fn from_seed(seed: &[u8]) -> Result<Self, WalletError> {
         if seed.len() < ed25519_dalek::SECRET_KEY_LENGTH {
-            return Err("Seed is too short".into());
+            return Err(WalletError::crypto("Seed is too short"));
         }
-        let secret_key = SecretKey::try_from(&seed[..ed25519_dalek::SECRET_KEY_LENGTH])?;
+        let secret_key = Zeroizing::new(
+            SecretKey::try_from(&seed[..ed25519_dalek::SECRET_KEY_LENGTH])
+                .map_err(WalletError::crypto)?,
+        );
         let signing_key = ed25519_dalek::SigningKey::from_bytes(&secret_key);
         Ok(Self(signing_key))
     }
 
     fn address(&self) -> Address {
-        write_as_base58(self.0.verifying_key().to_bytes().to_vec())
+        write_as_base58(self.0.verifying_key().as_bytes())
     }
 
     fn pk(&self) -> String {
-        write_as_base58(self.0.to_bytes().to_vec())
+        write_as_base58(Zeroizing::new(self.0.to_bytes()).as_slice())
+    }
+}
+
+/// Derives an ed25519 secret key from a seed as specified in SLIP-0010.
+fn derive_slip10(seed: &[u8], path: &DerivationPath) -> Result<Zeroizing<SecretKey>, WalletError> {
+    let (mut key, mut chain_code) = hmac_sha512(b"ed25519 seed", &[seed])?;
+    for child in path {
+        let ChildNumber::Hardened { index } = child else {
+            return Err(WalletError::invalid_key(
+                "SLIP-0010 ed25519 derivation supports only hardened indexes",
+            ));
+        };
+        let index = (index | 0x8000_0000).to_be_bytes();
+        (key, chain_code) = hmac_sha512(&chain_code, &[&[0], &key, &index])?;
+    }
+    Ok(Zeroizing::new(key))
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> Result<([u8; 32], [u8; 32]), WalletError> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).map_err(WalletError::crypto)?;
+    data.iter().for_each(|d| mac.update(d));
+    let result = mac.finalize().into_bytes();
+    let (left, right) = result.split_at(32);
+    // HMAC-SHA512 outputs 64 bytes.
+    Ok((left.try_into().unwrap(), right.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hex::{DisplayHex, FromHex};
+    use mnemonic::Mnemonic;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn keypair(derivation: &str, index: AddressIndex) -> Result<SolanaKeypair, WalletError> {
+        let seed = Mnemonic::new_from_phrase("en", PHRASE).unwrap().seed("");
+        SolanaKeypair::new(Net::Main, &seed, &derivation.parse().unwrap(), index)
+    }
+
+    #[test]
+    fn slip10_test_vector_1() {
+        let seed = Vec::<u8>::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        [
+            (
+                "m",
+                "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+            ),
+            (
+                "m/0'",
+                "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+            ),
+            (
+                "m/0'/1'",
+                "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+            ),
+        ]
+        .iter()
+        .for_each(|&(path, expected)| {
+            let key = derive_slip10(&seed, &DerivationPath::from_str(path).unwrap()).unwrap();
+            assert_eq!(key.to_lower_hex_string(), expected);
+        });
+    }
+
+    #[test]
+    fn default_derivation() {
+        assert_eq!(SolanaDerivation::default().to_string(), "bip44");
+        assert_eq!(
+            keypair("bip44", AddressIndex::default()).unwrap().address(),
+            "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+        );
+        assert_eq!(
+            keypair("bip44", AddressIndex::new(1, false, 0))
+                .unwrap()
+                .address(),
+            "Hh8QwFUA6MtVu1qAoq12ucvFHNwCcVTV7hpWjeY1Hztb"
+        );
+        assert_eq!(
+            keypair("bip44", AddressIndex::new(0, false, 5))
+                .unwrap()
+                .address(),
+            "A9dXuWYcryX43eLhFFZ2BmAXu32oRHY3hZ2xftfyXxWB"
+        );
+        assert!(keypair("bip44", AddressIndex::new(0, true, 0)).is_err());
+    }
+
+    #[test]
+    fn custom_path() {
+        assert_eq!(
+            keypair("m/44'/501'/0'/0'", AddressIndex::default())
+                .unwrap()
+                .address(),
+            "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+        );
+        assert_eq!(
+            keypair("m/44'/501'/1'/0'", AddressIndex::default())
+                .unwrap()
+                .address(),
+            "Hh8QwFUA6MtVu1qAoq12ucvFHNwCcVTV7hpWjeY1Hztb"
+        );
+        assert!(keypair("m/44'/501'/1'/0'", AddressIndex::new(0, false, 1)).is_err());
+    }
+
+    #[test]
+    fn legacy_derivation() {
+        assert_eq!(
+            keypair("legacy", AddressIndex::default())
+                .unwrap()
+                .address(),
+            "EHqmfkN89RJ7Y33CXM6uCzhVeuywHoJXZZLszBHHZy7o"
+        );
+        assert!(keypair("legacy", AddressIndex::new(1, false, 0)).is_err());
+    }
+
+    #[test]
+    fn non_hardened_path() {
+        assert!(SolanaDerivation::from_str("m/44'/501'/0'/0").is_err());
+    }
+
+    #[test]
+    fn debug_is_redacted() {
+        let keypair = keypair("bip44", AddressIndex::default()).unwrap();
+        let debug = format!("{keypair:?}");
+        assert_eq!(
+            debug,
+            format!("SolanaKeypair {{ address: {:?}, .. }}", keypair.address())
+        );
+        assert!(!debug.contains(&keypair.pk()));
     }
 }
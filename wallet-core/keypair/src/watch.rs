@@ -0,0 +1,164 @@
+use crate::account::{address_indexes, AddressIndex};
+use crate::bitcoin::BitcoinAccountXpub;
+use crate::BitcoinAddressType;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use types::error::WalletError;
+use types::shared::{Address, Blockchain, Net};
+
+/// The public keys of a single account, from which addresses are derived without any secret.
+pub struct WatchOnlyAccount {
+    net: Net,
+    key: WatchKey,
+}
+
+enum WatchKey {
+    Bitcoin(BitcoinAccountXpub),
+    /// A Solana public key, which is its only address.
+    Solana(Address),
+}
+
+impl WatchOnlyAccount {
+    /// Watches a Bitcoin extended public key (xpub/ypub/zpub/tpub/upub/vpub), a Bitcoin output
+    /// descriptor or a Solana public key.
+    ///
+    /// `bitcoin_address_type` is the script type of plain xpub/tpub keys, which don't encode it.
+    pub fn new(
+        net: Net,
+        key: &str,
+        bitcoin_address_type: Option<BitcoinAddressType>,
+    ) -> Result<Self, WalletError> {
+        let key = match bs58::decode(key.trim()).into_vec() {
+            Ok(bytes) if bytes.len() == ed25519_dalek::PUBLIC_KEY_LENGTH => {
+                ed25519_dalek::VerifyingKey::try_from(bytes.as_slice())
+                    .map_err(WalletError::invalid_key)?;
+                WatchKey::Solana(key.trim().to_string())
+            }
+            _ => WatchKey::Bitcoin(BitcoinAccountXpub::parse(net, key, bitcoin_address_type)?),
+        };
+        Ok(Self { net, key })
+    }
+
+    pub fn net(&self) -> Net {
+        self.net
+    }
+
+    pub fn blockchain(&self) -> Blockchain {
+        match self.key {
+            WatchKey::Bitcoin(_) => Blockchain::Bitcoin,
+            WatchKey::Solana(_) => Blockchain::Solana,
+        }
+    }
+
+    /// The watched Bitcoin extended public key, if any.
+    pub fn bitcoin_xpub(&self) -> Option<&BitcoinAccountXpub> {
+        match &self.key {
+            WatchKey::Bitcoin(xpub) => Some(xpub),
+            WatchKey::Solana(_) => None,
+        }
+    }
+
+    pub fn derive_address(
+        &self,
+        blockchain: Blockchain,
+        index: AddressIndex,
+    ) -> Result<Address, WalletError> {
+        if blockchain != self.blockchain() {
+            return Err(WalletError::UnsupportedBlockchain(format!(
+                "The wallet only watches {} addresses",
+                self.blockchain()
+            )));
+        }
+        match &self.key {
+            WatchKey::Bitcoin(xpub) => xpub.derive_address(index),
+            WatchKey::Solana(_) if index != AddressIndex::default() => {
+                Err(WalletError::InvalidInput(
+                    "A watched Solana public key has a single address".to_string(),
+                ))
+            }
+            WatchKey::Solana(address) => Ok(address.clone()),
+        }
+    }
+
+    /// Lists the first `count` receive addresses of the account, followed by its first
+    /// `count` change addresses on blockchains that use them.
+    pub fn list_addresses(
+        &self,
+        blockchain: Blockchain,
+        account: u32,
+        count: u32,
+    ) -> Result<Vec<(AddressIndex, Address)>, WalletError> {
+        if let WatchKey::Solana(_) = self.key {
+            let index = AddressIndex::new(account, false, 0);
+            return Ok(vec![(index, self.derive_address(blockchain, index)?)]);
+        }
+        address_indexes(blockchain, account, count)
+            .map(|index| Ok((index, self.derive_address(blockchain, index)?)))
+            .collect()
+    }
+}
+
+impl Display for WatchOnlyAccount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Network: {}\n", self.net)?;
+        match &self.key {
+            WatchKey::Bitcoin(xpub) => {
+                writeln!(f, "Bitcoin ({}): {}", xpub.address_type(), xpub.slip132())?;
+                writeln!(f, "Receive descriptor: {}", xpub.descriptor(false))?;
+                writeln!(f, "Change descriptor: {}", xpub.descriptor(true))
+            }
+            WatchKey::Solana(address) => writeln!(f, "Solana: {}", address),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::Account;
+    use crate::Derivation;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const ZPUB: &str = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+
+    fn account() -> Account {
+        Account::restore_from_seed(Net::Main, "en", PHRASE, "", &Derivation::default()).unwrap()
+    }
+
+    #[test]
+    fn watch_bitcoin_xpub() {
+        let watch = WatchOnlyAccount::new(Net::Main, ZPUB, None).unwrap();
+        assert_eq!(watch.blockchain(), Blockchain::Bitcoin);
+        assert_eq!(
+            watch.list_addresses(Blockchain::Bitcoin, 0, 3).unwrap(),
+            account().list_addresses(Blockchain::Bitcoin, 0, 3).unwrap()
+        );
+        assert!(watch
+            .derive_address(Blockchain::Solana, AddressIndex::default())
+            .is_err());
+    }
+
+    #[test]
+    fn watch_solana_public_key() {
+        let address = "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk";
+        let watch = WatchOnlyAccount::new(Net::Main, address, None).unwrap();
+        assert_eq!(watch.blockchain(), Blockchain::Solana);
+        assert_eq!(
+            watch
+                .derive_address(Blockchain::Solana, AddressIndex::default())
+                .unwrap(),
+            address
+        );
+        assert!(watch
+            .derive_address(Blockchain::Solana, AddressIndex::new(0, false, 1))
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_key() {
+        assert!(matches!(
+            WatchOnlyAccount::new(Net::Main, "not a key", None),
+            Err(WalletError::InvalidKey(_))
+        ));
+    }
+}
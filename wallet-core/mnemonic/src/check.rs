@@ -0,0 +1,453 @@
+use bip39::Language;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use zeroize::Zeroizing;
+
+/// The number of words of a BIP-39 seed phrase.
+const WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// The number of words in a BIP-39 wordlist.
+const WORDLIST_LEN: u16 = 2048;
+
+/// The length of the prefix that identifies a word of the BIP-39 wordlists.
+const PREFIX_LEN: usize = 4;
+
+/// Suggestions for an unknown word are at most this many edits away from it.
+const MAX_DISTANCE: usize = 2;
+
+const MAX_SUGGESTIONS: usize = 5;
+
+/// What is wrong with a seed phrase, checked in this order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MnemonicStatus {
+    Valid,
+    /// Some words aren't in the wordlist.
+    UnknownWords,
+    /// One word too few for a seed phrase.
+    MissingWord,
+    /// Not 12, 15, 18, 21 or 24 words, nor one word less.
+    WrongWordCount,
+    /// Known words in the right number, but one of them is wrong.
+    InvalidChecksum,
+}
+
+/// A word of a seed phrase that isn't in the wordlist. Its `Debug` shows its position only.
+#[derive(Clone, PartialEq, Eq)]
+pub struct UnknownWord {
+    /// The position of the word in the phrase, from 1.
+    pub position: usize,
+    pub word: String,
+    /// The words of the wordlist that start with the same 4 letters, then the closest ones.
+    pub suggestions: Vec<&'static str>,
+}
+
+/// How a [`Candidate`] changes the phrase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repair {
+    /// Replaces the word at the position.
+    Replace,
+    /// Is inserted before the word at the position, or at the end.
+    Insert,
+}
+
+/// A word of the wordlist that gives the phrase a valid checksum. Its `Debug` shows its
+/// position only.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    /// The position of the word in the repaired phrase, from 1.
+    pub position: usize,
+    pub word: &'static str,
+    pub repair: Repair,
+}
+
+/// The validation of a seed phrase, with the words that could repair it. Its `Debug` shows
+/// positions and counts, never words.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MnemonicCheck {
+    pub word_count: usize,
+    pub status: MnemonicStatus,
+    pub unknown_words: Vec<UnknownWord>,
+    /// The phrases with a valid checksum that differ by one word, when a single word is
+    /// unknown, missing or wrong. The replacements of an unknown word come in the order of its
+    /// suggestions, then closest first.
+    pub candidates: Vec<Candidate>,
+}
+
+impl MnemonicCheck {
+    pub(crate) fn new(language: Language, phrase: &str) -> Self {
+        let mut check = Self::status(language, phrase);
+        let wordlist = (0..WORDLIST_LEN)
+            .map(|bits| language.wordlist().get_word(bits.into()))
+            .collect::<Vec<_>>();
+        for unknown in &mut check.unknown_words {
+            unknown.suggestions = suggestions(&wordlist, &unknown.word);
+        }
+
+        let words = phrase.split_whitespace().collect::<Vec<_>>();
+        let repairs = Repairs {
+            language,
+            wordlist: &wordlist,
+            words: &words,
+        };
+        check.candidates = match check.status {
+            MnemonicStatus::UnknownWords => match check.unknown_words.as_slice() {
+                [unknown] if WORD_COUNTS.contains(&words.len()) => {
+                    let mut candidates = repairs.at(unknown.position - 1, Repair::Replace);
+                    candidates.sort_by_key(|candidate| {
+                        let suggested = unknown
+                            .suggestions
+                            .iter()
+                            .position(|&word| word == candidate.word);
+                        (
+                            suggested.unwrap_or(usize::MAX),
+                            distance(&unknown.word, candidate.word),
+                            candidate.word,
+                        )
+                    });
+                    candidates
+                }
+                _ => vec![],
+            },
+            MnemonicStatus::MissingWord => (0..=words.len())
+                .flat_map(|i| repairs.at(i, Repair::Insert))
+                .collect(),
+            MnemonicStatus::InvalidChecksum => (0..words.len())
+                .flat_map(|i| repairs.at(i, Repair::Replace))
+                .collect(),
+            MnemonicStatus::Valid | MnemonicStatus::WrongWordCount => vec![],
+        };
+        check
+    }
+
+    /// The status of a phrase and the positions of its unknown words, without the suggestions
+    /// and candidates that [`MnemonicCheck::new`] searches for.
+    pub(crate) fn status(language: Language, phrase: &str) -> Self {
+        let words = phrase.split_whitespace().collect::<Vec<_>>();
+        let unknown_words = words
+            .iter()
+            .enumerate()
+            .filter(|(_, word)| language.wordmap().get_bits(word).is_none())
+            .map(|(i, word)| UnknownWord {
+                position: i + 1,
+                word: word.to_string(),
+                suggestions: vec![],
+            })
+            .collect::<Vec<_>>();
+        let status = if !unknown_words.is_empty() {
+            MnemonicStatus::UnknownWords
+        } else if WORD_COUNTS.contains(&(words.len() + 1)) {
+            MnemonicStatus::MissingWord
+        } else if !WORD_COUNTS.contains(&words.len()) {
+            MnemonicStatus::WrongWordCount
+        } else if bip39::Mnemonic::validate(&words.join(" "), language).is_err() {
+            MnemonicStatus::InvalidChecksum
+        } else {
+            MnemonicStatus::Valid
+        };
+
+        Self {
+            word_count: words.len(),
+            status,
+            unknown_words,
+            candidates: vec![],
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.status == MnemonicStatus::Valid
+    }
+
+    /// The problem with the unknown words and their suggestions, which are parts of the seed
+    /// phrase, to be shown only to whoever asked for the check.
+    pub fn details(&self) -> Details<'_> {
+        Details(self)
+    }
+}
+
+impl Display for MnemonicCheck {
+    /// A summary of the problem with the positions of the words only, so that it can end up
+    /// in errors and logs.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.status {
+            MnemonicStatus::Valid => write!(f, "The seed phrase is valid"),
+            MnemonicStatus::UnknownWords => match self.unknown_words.as_slice() {
+                [unknown] => write!(f, "Word {} is not in the wordlist", unknown.position),
+                unknown => {
+                    let positions = unknown
+                        .iter()
+                        .map(|unknown| unknown.position.to_string())
+                        .collect::<Vec<_>>();
+                    write!(f, "Words {} are not in the wordlist", positions.join(", "))
+                }
+            },
+            MnemonicStatus::MissingWord => write!(
+                f,
+                "The seed phrase has {} words, one is missing",
+                self.word_count
+            ),
+            MnemonicStatus::WrongWordCount => write!(
+                f,
+                "The seed phrase has {} words instead of 12, 15, 18, 21 or 24",
+                self.word_count
+            ),
+            MnemonicStatus::InvalidChecksum => write!(
+                f,
+                "The checksum of the seed phrase doesn't match, a word is wrong"
+            ),
+        }
+    }
+}
+
+impl fmt::Debug for UnknownWord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnknownWord")
+            .field("position", &self.position)
+            .field("suggestions", &self.suggestions.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for Candidate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Candidate")
+            .field("position", &self.position)
+            .field("repair", &self.repair)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for MnemonicCheck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MnemonicCheck")
+            .field("word_count", &self.word_count)
+            .field("status", &self.status)
+            .field("unknown_words", &self.unknown_words)
+            .field("candidates", &self.candidates.len())
+            .finish()
+    }
+}
+
+/// The problem with a seed phrase, with its unknown words and their suggestions.
+pub struct Details<'a>(&'a MnemonicCheck);
+
+impl Display for Details<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0.status {
+            MnemonicStatus::UnknownWords => {
+                for (i, unknown) in self.0.unknown_words.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ". ")?;
+                    }
+                    write!(
+                        f,
+                        "Word {} '{}' is not in the wordlist",
+                        unknown.position, unknown.word
+                    )?;
+                    if !unknown.suggestions.is_empty() {
+                        write!(f, ", did you mean {}?", unknown.suggestions.join(", "))?;
+                    }
+                }
+                Ok(())
+            }
+            _ => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// Finds the words that repair a phrase at a position.
+struct Repairs<'a> {
+    language: Language,
+    wordlist: &'a [&'static str],
+    words: &'a [&'a str],
+}
+
+impl Repairs<'_> {
+    /// The words of the wordlist that give the phrase a valid checksum when they replace the
+    /// word at `index`, or are inserted there.
+    fn at(&self, index: usize, repair: Repair) -> Vec<Candidate> {
+        let rest = match repair {
+            Repair::Replace => index + 1,
+            Repair::Insert => index,
+        };
+        self.wordlist
+            .iter()
+            .filter(|&&word| {
+                let phrase = Zeroizing::new(
+                    self.words[..index]
+                        .iter()
+                        .chain([&word])
+                        .chain(&self.words[rest..])
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                );
+                self.words.get(index) != Some(&word)
+                    && bip39::Mnemonic::validate(&phrase, self.language).is_ok()
+            })
+            .map(|&word| Candidate {
+                position: index + 1,
+                word,
+                repair,
+            })
+            .collect()
+    }
+}
+
+/// The words of the wordlist that start with the first 4 letters of `word`, which identify a
+/// word, followed by the closest other words.
+fn suggestions(wordlist: &[&'static str], word: &str) -> Vec<&'static str> {
+    let prefix = word.chars().take(PREFIX_LEN).collect::<String>();
+    let mut suggestions = wordlist
+        .iter()
+        .filter(|candidate| candidate.starts_with(&prefix))
+        .copied()
+        .collect::<Vec<_>>();
+    if suggestions.len() > MAX_SUGGESTIONS {
+        suggestions.clear();
+    }
+    let mut closest = wordlist
+        .iter()
+        .map(|&candidate| (distance(word, candidate), candidate))
+        .filter(|&(distance, candidate)| {
+            distance <= MAX_DISTANCE && !suggestions.contains(&candidate)
+        })
+        .collect::<Vec<_>>();
+    closest.sort();
+    suggestions.extend(closest.into_iter().map(|(_, candidate)| candidate));
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+/// The number of letters inserted, deleted, substituted or swapped with the next one to turn
+/// `a` into `b` (the optimal string alignment distance).
+fn distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // `rows[i][j]` is the distance between the first `i` letters of `a` and `j` letters of `b`.
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str =
+        "brisk fossil brisk dream dry sing lottery mountain sound void refuse pumpkin";
+
+    fn check(phrase: &str) -> MnemonicCheck {
+        MnemonicCheck::new(Language::English, phrase)
+    }
+
+    #[test]
+    fn valid() {
+        let check = check(PHRASE);
+        assert!(check.is_valid());
+        assert_eq!(check.word_count, 12);
+        assert!(check.unknown_words.is_empty());
+        assert!(check.candidates.is_empty());
+    }
+
+    #[test]
+    fn distances() {
+        assert_eq!(distance("abandon", "abandon"), 0);
+        assert_eq!(distance("abandn", "abandon"), 1);
+        assert_eq!(distance("fsosil", "fossil"), 1);
+        assert_eq!(distance("", "act"), 3);
+    }
+
+    #[test]
+    fn misspelled_word() {
+        let check = check(&PHRASE.replace("fossil", "fsosil"));
+        assert_eq!(check.status, MnemonicStatus::UnknownWords);
+        assert_eq!(
+            check.unknown_words,
+            [UnknownWord {
+                position: 2,
+                word: "fsosil".to_string(),
+                suggestions: vec!["fossil", "foil"],
+            }]
+        );
+        assert_eq!(
+            check.candidates[0],
+            Candidate {
+                position: 2,
+                word: "fossil",
+                repair: Repair::Replace,
+            }
+        );
+        // A 4-bit checksum lets about one word in 16 through.
+        assert!(check.candidates.len() > 50);
+        assert_eq!(
+            check.details().to_string(),
+            "Word 2 'fsosil' is not in the wordlist, did you mean fossil, foil?"
+        );
+        assert_eq!(check.to_string(), "Word 2 is not in the wordlist");
+
+        let two_words = MnemonicCheck::new(
+            Language::English,
+            &PHRASE.replace("fossil", "fsosil").replace("void", "viod"),
+        );
+        assert_eq!(two_words.to_string(), "Words 2, 10 are not in the wordlist");
+    }
+
+    #[test]
+    fn unique_prefix() {
+        let check = check(&PHRASE.replace("lottery", "lott"));
+        assert_eq!(check.unknown_words[0].suggestions[0], "lottery");
+        assert_eq!(check.candidates[0].word, "lottery");
+    }
+
+    #[test]
+    fn missing_word() {
+        let check = check(&PHRASE.replace(" dream", ""));
+        assert_eq!(check.status, MnemonicStatus::MissingWord);
+        assert!(check.candidates.contains(&Candidate {
+            position: 4,
+            word: "dream",
+            repair: Repair::Insert,
+        }));
+        assert!(check
+            .candidates
+            .iter()
+            .all(|candidate| candidate.repair == Repair::Insert));
+    }
+
+    #[test]
+    fn wrong_word() {
+        let check = check(&PHRASE.replace("void", "vote"));
+        assert_eq!(check.status, MnemonicStatus::InvalidChecksum);
+        assert!(check.candidates.contains(&Candidate {
+            position: 10,
+            word: "void",
+            repair: Repair::Replace,
+        }));
+        assert!(!check
+            .candidates
+            .iter()
+            .any(|candidate| candidate.position == 10 && candidate.word == "vote"));
+    }
+
+    #[test]
+    fn wrong_word_count() {
+        let check = check("brisk fossil brisk");
+        assert_eq!(check.status, MnemonicStatus::WrongWordCount);
+        assert!(check.candidates.is_empty());
+    }
+}
@@ -1,7 +1,12 @@
 use bip39::{Language, MnemonicType, Seed};
-use std::error::Error;
 use std::fmt;
 use std::fmt::Formatter;
+use types::error::WalletError;
+use zeroize::Zeroizing;
+
+mod check;
+
+pub use crate::check::{Candidate, Details, MnemonicCheck, MnemonicStatus, Repair, UnknownWord};
 
 #[derive(Debug, Copy, Clone)]
 pub enum WordCount {
@@ -36,7 +41,9 @@ impl From<WordCount> for MnemonicType {
     }
 }
 
-#[derive(Clone)]
+/// A seed phrase, zeroized on drop by the underlying `bip39::Mnemonic`.
+///
+/// It isn't `Clone`, so that copies of the phrase are only made by parsing it again.
 pub struct Mnemonic {
     internal: bip39::Mnemonic,
 }
@@ -52,12 +59,28 @@ impl Mnemonic {
         }
     }
 
-    pub fn new_from_phrase(language_code: &str, phrase: &str) -> Result<Self, Box<dyn Error>> {
+    /// Parses a seed phrase, failing with what [`Mnemonic::check`] finds wrong with it, without
+    /// the words of the phrase.
+    pub fn new_from_phrase(language_code: &str, phrase: &str) -> Result<Self, WalletError> {
+        let language = get_language(language_code);
         Ok(Self {
-            internal: bip39::Mnemonic::from_phrase(phrase, get_language(language_code))?,
+            internal: bip39::Mnemonic::from_phrase(phrase, language).map_err(|e| {
+                let check = MnemonicCheck::status(language, phrase);
+                WalletError::InvalidMnemonic(if check.is_valid() {
+                    e.to_string()
+                } else {
+                    check.to_string()
+                })
+            })?,
         })
     }
 
+    /// Validates a seed phrase, suggesting words of the wordlist for the unknown ones and the
+    /// words that make the checksum valid when one is unknown, missing or wrong.
+    pub fn check(language_code: &str, phrase: &str) -> MnemonicCheck {
+        MnemonicCheck::new(get_language(language_code), phrase)
+    }
+
     pub fn len(&self) -> usize {
         self.seed_phrase().len()
     }
@@ -70,19 +93,18 @@ impl Mnemonic {
         self.internal.phrase()
     }
 
-    pub fn seed(&self, passphrase: &str) -> Vec<u8> {
-        Vec::from(Seed::new(&self.internal, passphrase).as_bytes())
+    /// The BIP-39 seed, zeroized on drop.
+    pub fn seed(&self, passphrase: &str) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(Vec::from(Seed::new(&self.internal, passphrase).as_bytes()))
     }
 }
 
-impl fmt::Display for Mnemonic {
+impl fmt::Debug for Mnemonic {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let phrase = self.seed_phrase();
-        let divider = String::from_utf8(vec![b'='; phrase.len()]).unwrap();
-        f.write_str(&format!(
-            "{}\nSave this seed phrase to recover your key:\n{}\n{}",
-            &divider, phrase, &divider
-        ))
+        f.debug_struct("Mnemonic")
+            .field("language", &self.internal.language())
+            .field("words", &self.seed_phrase().split_whitespace().count())
+            .finish_non_exhaustive()
     }
 }
 
@@ -122,7 +144,7 @@ mod tests {
     #[test]
     fn seed() {
         assert_eq!(
-            Mnemonic::new_from_phrase(
+            *Mnemonic::new_from_phrase(
                 "en",
                 "brisk fossil brisk dream dry sing lottery mountain sound void refuse pumpkin"
             )
@@ -140,7 +162,7 @@ mod tests {
     #[test]
     fn seed_with_pass() {
         assert_eq!(
-            Mnemonic::new_from_phrase(
+            *Mnemonic::new_from_phrase(
                 "en",
                 "brisk fossil brisk dream dry sing lottery mountain sound void refuse pumpkin"
             )
@@ -154,4 +176,54 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn invalid_phrase() {
+        assert!(matches!(
+            Mnemonic::new_from_phrase("en", "brisk fossil brisk"),
+            Err(WalletError::InvalidMnemonic(_))
+        ));
+        assert_eq!(
+            Mnemonic::new_from_phrase(
+                "en",
+                "brisk fossil brisk dream dry sing lotery mountain sound void refuse pumpkin"
+            )
+            .unwrap_err()
+            .to_string(),
+            "Invalid mnemonic: Word 7 is not in the wordlist"
+        );
+    }
+
+    #[test]
+    fn debug_is_redacted() {
+        let mnemonic = Mnemonic::new_from_phrase(
+            "en",
+            "brisk fossil brisk dream dry sing lottery mountain sound void refuse pumpkin",
+        )
+        .unwrap();
+        let debug = format!("{mnemonic:?}");
+        assert_eq!(debug, "Mnemonic { language: English, words: 12, .. }");
+        assert!(!debug.contains("brisk"));
+    }
+
+    #[test]
+    fn check_debug_is_redacted() {
+        let check = Mnemonic::check(
+            "en",
+            "brisk fsosil brisk dream dry sing lottery mountain sound void refuse pumpkin",
+        );
+        let debug = format!("{check:?}");
+        assert_eq!(
+            debug,
+            "MnemonicCheck { word_count: 12, status: UnknownWords, unknown_words: \
+             [UnknownWord { position: 2, suggestions: 2, .. }], candidates: 120 }"
+        );
+        assert_eq!(
+            format!("{:?}", check.candidates[0]),
+            "Candidate { position: 2, repair: Replace, .. }"
+        );
+        for word in ["brisk", "fsosil", "fossil", "foil"] {
+            assert!(!debug.contains(word), "{word}");
+        }
+    }
 }
@@ -0,0 +1,214 @@
+use crate::error::WalletError;
+use crate::shared::{Address, Blockchain};
+use crate::units::{format_units, parse_units, ParseUnitsError, U256};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// What an amount is counted in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Asset {
+    /// The native coin of a blockchain: BTC, ETH or SOL.
+    Native { blockchain: Blockchain },
+    /// A fungible token: an SPL mint on Solana, an ERC-20 contract on Ethereum.
+    Token {
+        blockchain: Blockchain,
+        address: Address,
+        symbol: Option<String>,
+    },
+}
+
+impl Asset {
+    pub fn blockchain(&self) -> Blockchain {
+        match self {
+            Asset::Native { blockchain } | Asset::Token { blockchain, .. } => *blockchain,
+        }
+    }
+
+    /// The mint or contract address of a token.
+    pub fn address(&self) -> Option<&Address> {
+        match self {
+            Asset::Native { .. } => None,
+            Asset::Token { address, .. } => Some(address),
+        }
+    }
+
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            Asset::Native { blockchain } => Some(blockchain.symbol()),
+            Asset::Token { symbol, .. } => symbol.as_deref(),
+        }
+    }
+}
+
+impl Display for Asset {
+    /// The symbol, or the address of a token without one.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (self.symbol(), self.address()) {
+            (Some(symbol), _) => write!(f, "{symbol}"),
+            (None, Some(address)) => write!(f, "{address}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// An exact amount of an asset, kept in base units so that it is never rounded.
+///
+/// It is serialized with the base units as a decimal string, since they may not fit in a JSON
+/// number: `{"asset": {"type": "native", "blockchain": "ethereum"}, "units": "1500000000000000000",
+/// "decimals": 18}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Amount {
+    pub asset: Asset,
+    /// Satoshis, wei, lamports or the smallest unit of a token.
+    #[serde(with = "decimal")]
+    pub units: U256,
+    pub decimals: u8,
+}
+
+impl Amount {
+    pub fn new(asset: Asset, units: impl Into<U256>, decimals: u8) -> Self {
+        Self {
+            asset,
+            units: units.into(),
+            decimals,
+        }
+    }
+
+    /// An amount of the native coin of `blockchain`, in its base units.
+    pub fn native(blockchain: Blockchain, units: impl Into<U256>) -> Self {
+        Amount::new(
+            Asset::Native { blockchain },
+            units,
+            blockchain.decimals() as u8,
+        )
+    }
+
+    /// Parses an amount typed by a user in whole coins or tokens, e.g. `0.1` SOL.
+    pub fn parse(asset: Asset, decimals: u8, input: &str) -> Result<Self, ParseUnitsError> {
+        Ok(Amount::new(
+            asset,
+            parse_units(input, decimals as u32)?,
+            decimals,
+        ))
+    }
+
+    /// Parses an amount of the native coin of `blockchain`, e.g. `0.0005` BTC.
+    pub fn parse_native(blockchain: Blockchain, input: &str) -> Result<Self, ParseUnitsError> {
+        Amount::parse(
+            Asset::Native { blockchain },
+            blockchain.decimals() as u8,
+            input,
+        )
+    }
+
+    /// The amount in whole coins or tokens, e.g. `12.5`.
+    pub fn ui_amount(&self) -> String {
+        format_units(self.units, self.decimals as u32)
+    }
+
+    /// The base units, for blockchains that count them in 64 bits: satoshis and lamports.
+    pub fn to_u64(&self) -> Result<u64, WalletError> {
+        u64::try_from(self.units).map_err(|_| {
+            WalletError::InvalidAmount(format!("{self} exceeds {} base units", u64::MAX))
+        })
+    }
+
+    /// The sum of two amounts of the same asset, or `None` if the assets differ or it
+    /// overflows.
+    pub fn checked_add(&self, other: &Amount) -> Option<Amount> {
+        if self.asset != other.asset || self.decimals != other.decimals {
+            return None;
+        }
+        Some(Amount::new(
+            self.asset.clone(),
+            self.units.checked_add(other.units)?,
+            self.decimals,
+        ))
+    }
+}
+
+impl Display for Amount {
+    /// E.g. `0.0015 BTC`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.ui_amount(), self.asset)
+    }
+}
+
+/// Serializes a `U256` as a decimal string.
+mod decimal {
+    use super::*;
+    use serde::de::Error;
+
+    pub(super) fn serialize<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<U256, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        U256::from_dec_str(&value).map_err(|_| D::Error::custom(format!("Invalid amount: {value}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let sol = Amount::parse_native(Blockchain::Solana, "0.1").unwrap();
+        assert_eq!(sol.units, U256::from(100_000_000));
+        assert_eq!(sol.to_string(), "0.1 SOL");
+        assert_eq!(sol.to_u64().unwrap(), 100_000_000);
+
+        let btc = Amount::parse_native(Blockchain::Bitcoin, "0.0005").unwrap();
+        assert_eq!(btc.to_u64().unwrap(), 50_000);
+        assert!(Amount::parse_native(Blockchain::Bitcoin, "0.000000001").is_err());
+
+        // 100 ETH in wei doesn't fit in 64 bits.
+        let eth = Amount::parse_native(Blockchain::Ethereum, "100").unwrap();
+        assert_eq!(eth.units, U256::exp10(20));
+        assert_eq!(eth.to_string(), "100 ETH");
+        assert!(matches!(eth.to_u64(), Err(WalletError::InvalidAmount(_))));
+
+        let usdc = Asset::Token {
+            blockchain: Blockchain::Ethereum,
+            address: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
+            symbol: Some("USDC".to_string()),
+        };
+        assert_eq!(
+            Amount::parse(usdc, 6, "12.5").unwrap().to_string(),
+            "12.5 USDC"
+        );
+    }
+
+    #[test]
+    fn checked_add() {
+        let eth = |units: u64| Amount::native(Blockchain::Ethereum, units);
+        assert_eq!(eth(1).checked_add(&eth(2)), Some(eth(3)));
+        assert_eq!(
+            eth(1).checked_add(&Amount::native(Blockchain::Solana, 2)),
+            None
+        );
+        let max = Amount::native(Blockchain::Ethereum, U256::MAX);
+        assert_eq!(max.checked_add(&eth(1)), None);
+    }
+
+    #[test]
+    fn serialize() {
+        let eth = Amount::native(Blockchain::Ethereum, U256::exp10(20));
+        let json = serde_json::to_value(&eth).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "asset": {"type": "native", "blockchain": "ethereum"},
+                "units": "100000000000000000000",
+                "decimals": 18
+            })
+        );
+        assert_eq!(serde_json::from_value::<Amount>(json).unwrap(), eth);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::amount::Amount;
+use crate::units::ParseUnitsError;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use thiserror::Error;
+
+pub type WalletResult<T> = Result<T, WalletError>;
+
+/// The errors of all wallet-core crates.
+///
+/// Errors of the underlying libraries are kept as messages, so that the error can be cloned,
+/// sent across threads and serialized for the GUI, e.g.
+/// `{"kind": "rpc", "details": {"code": -32000, "message": "nonce too low"}}`.
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "details", rename_all = "snake_case")]
+pub enum WalletError {
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+    /// An operation that the blockchain or wallet type doesn't support.
+    #[error("{0}")]
+    UnsupportedBlockchain(String),
+    /// A wallet or command used on another network than its own, or an operation that isn't
+    /// available on the network, like an airdrop on Main-net.
+    #[error("Wrong network: {0}")]
+    WrongNetwork(String),
+    /// An argument that is invalid for the operation, e.g. an unknown network name or a change
+    /// address on Solana.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    /// An unparsable key, extended key, descriptor or derivation path.
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+    /// A key derivation or signature that failed inside a cryptographic library.
+    #[error("Cryptographic error: {0}")]
+    Crypto(String),
+    /// An error returned by a node or API server.
+    #[error("RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    /// The node or API server couldn't be reached, or answered garbage.
+    #[error("Network error: {0}")]
+    Network(String),
+    /// A request to a node or API server that wasn't answered within its timeout.
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+    /// A balance of a coin or token, fee included, that can't pay for a transaction.
+    #[error("Insufficient funds: {available} available, {needed} needed")]
+    InsufficientFunds {
+        needed: Box<Amount>,
+        available: Box<Amount>,
+    },
+    /// A transaction that was refused, reverted or not confirmed in time.
+    #[error("Transaction failed: {0}")]
+    Transaction(String),
+    #[error("Wallet '{wallet}' is watch-only and can't sign")]
+    WatchOnly { wallet: String },
+    #[error("Wallet '{0}' not found")]
+    WalletNotFound(String),
+    #[error("Wallet '{0}' already exists")]
+    WalletExists(String),
+    #[error("Wrong password")]
+    WrongPassword,
+    /// An unreadable keystore or config file.
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+impl WalletError {
+    pub fn invalid_address(error: impl Display) -> Self {
+        WalletError::InvalidAddress(error.to_string())
+    }
+
+    pub fn invalid_key(error: impl Display) -> Self {
+        WalletError::InvalidKey(error.to_string())
+    }
+
+    pub fn crypto(error: impl Display) -> Self {
+        WalletError::Crypto(error.to_string())
+    }
+
+    pub fn insufficient_funds(needed: Amount, available: Amount) -> Self {
+        WalletError::InsufficientFunds {
+            needed: Box::new(needed),
+            available: Box::new(available),
+        }
+    }
+
+    pub fn network(error: impl Display) -> Self {
+        WalletError::Network(error.to_string())
+    }
+
+    pub fn storage(error: impl Display) -> Self {
+        WalletError::Storage(error.to_string())
+    }
+}
+
+impl From<ParseUnitsError> for WalletError {
+    fn from(error: ParseUnitsError) -> Self {
+        WalletError::InvalidAmount(error.to_string())
+    }
+}
+
+impl From<std::io::Error> for WalletError {
+    fn from(error: std::io::Error) -> Self {
+        WalletError::storage(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::Blockchain;
+    use crate::units::parse_units;
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    #[test]
+    fn send_sync() {
+        assert_send_sync::<WalletError>();
+    }
+
+    #[test]
+    fn serialize() {
+        let error = WalletError::Rpc {
+            code: -32000,
+            message: "nonce too low".to_string(),
+        };
+        assert_eq!(error.to_string(), "RPC error -32000: nonce too low");
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"kind": "rpc", "details": {"code": -32000, "message": "nonce too low"}})
+        );
+        assert_eq!(serde_json::from_value::<WalletError>(json).unwrap(), error);
+        assert_eq!(
+            serde_json::to_value(WalletError::WrongPassword).unwrap(),
+            serde_json::json!({"kind": "wrong_password"})
+        );
+    }
+
+    #[test]
+    fn insufficient_funds() {
+        let error = WalletError::insufficient_funds(
+            Amount::native(Blockchain::Bitcoin, 150_000u64),
+            Amount::native(Blockchain::Bitcoin, 100_000u64),
+        );
+        assert_eq!(
+            error.to_string(),
+            "Insufficient funds: 0.001 BTC available, 0.0015 BTC needed"
+        );
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["details"]["needed"]["units"], "150000");
+        assert_eq!(serde_json::from_value::<WalletError>(json).unwrap(), error);
+    }
+
+    #[test]
+    fn from_parse_units() {
+        assert!(matches!(
+            WalletError::from(parse_units("1.2.3", 8).unwrap_err()),
+            WalletError::InvalidAmount(_)
+        ));
+    }
+}
@@ -1,2 +1,5 @@
+pub mod amount;
 pub mod constants;
+pub mod error;
 pub mod shared;
+pub mod units;
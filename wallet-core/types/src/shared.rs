@@ -1,9 +1,11 @@
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Blockchain {
     Bitcoin,
     Ethereum,
@@ -18,6 +20,24 @@ impl Blockchain {
             Blockchain::Solana,
         ]
     }
+
+    /// The ticker of the native coin.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Blockchain::Bitcoin => "BTC",
+            Blockchain::Ethereum => "ETH",
+            Blockchain::Solana => "SOL",
+        }
+    }
+
+    /// The number of decimals of the native coin: satoshis, wei and lamports.
+    pub fn decimals(&self) -> u32 {
+        match self {
+            Blockchain::Bitcoin => 8,
+            Blockchain::Ethereum => 18,
+            Blockchain::Solana => 9,
+        }
+    }
 }
 
 impl Display for Blockchain {
@@ -54,7 +74,8 @@ impl FromStr for Blockchain {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Net {
     Main,
     Test,
@@ -62,6 +83,18 @@ pub enum Net {
     Local,
 }
 
+impl Net {
+    /// The name used in commands and in the config, e.g. `main`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Net::Main => "main",
+            Net::Test => "test",
+            Net::Dev => "dev",
+            Net::Local => "local",
+        }
+    }
+}
+
 impl Display for Net {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
@@ -77,6 +110,17 @@ impl Display for Net {
     }
 }
 
+/// The Bitcoin network of a net: regtest for the dev and local nets.
+impl From<Net> for bitcoin::Network {
+    fn from(net: Net) -> Self {
+        match net {
+            Net::Main => bitcoin::Network::Bitcoin,
+            Net::Test => bitcoin::Network::Testnet,
+            Net::Dev | Net::Local => bitcoin::Network::Regtest,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ParseNetError;
 
@@ -92,13 +136,73 @@ impl FromStr for Net {
     type Err = ParseNetError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        match input.to_lowercase().as_str() {
-            "main" => Ok(Net::Main),
-            "test" => Ok(Net::Test),
-            "dev" => Ok(Net::Dev),
-            "local" => Ok(Net::Local),
-            _ => Err(ParseNetError),
-        }
+        [Net::Main, Net::Test, Net::Dev, Net::Local]
+            .into_iter()
+            .find(|net| net.name().eq_ignore_ascii_case(input))
+            .ok_or(ParseNetError)
+    }
+}
+
+/// How final a submitted transaction must be before it is considered confirmed.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Commitment {
+    /// Included in a block that may still be dropped.
+    Processed,
+    /// Voted on by a supermajority of the cluster, or included in a block on Bitcoin and
+    /// Ethereum.
+    #[default]
+    Confirmed,
+    /// Rooted, and can no longer be rolled back.
+    Finalized,
+}
+
+impl Commitment {
+    pub fn all_variants() -> &'static [Commitment] {
+        &[
+            Commitment::Processed,
+            Commitment::Confirmed,
+            Commitment::Finalized,
+        ]
+    }
+}
+
+impl Display for Commitment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Commitment::Processed => "processed",
+                Commitment::Confirmed => "confirmed",
+                Commitment::Finalized => "finalized",
+            }
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseCommitmentError;
+
+impl Display for ParseCommitmentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid commitment, expected processed, confirmed or finalized"
+        )
+    }
+}
+
+impl Error for ParseCommitmentError {}
+
+impl FromStr for Commitment {
+    type Err = ParseCommitmentError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Commitment::all_variants()
+            .iter()
+            .find(|c| c.to_string().eq_ignore_ascii_case(input))
+            .copied()
+            .ok_or(ParseCommitmentError)
     }
 }
 
@@ -0,0 +1,107 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+pub use u256::U256;
+
+// The code generated by `construct_uint!` trips a lint of recent clippy versions.
+#[allow(clippy::manual_div_ceil)]
+mod u256 {
+    uint::construct_uint! {
+        /// A 256-bit unsigned integer, wide enough for any amount of base units, e.g. wei.
+        pub struct U256(4);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseUnitsError(String);
+
+impl Display for ParseUnitsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid amount: {}", self.0)
+    }
+}
+
+impl Error for ParseUnitsError {}
+
+/// Parses a decimal amount of coins, e.g. `"0.1"` SOL, into base units, e.g. lamports,
+/// without rounding.
+pub fn parse_units(input: &str, decimals: u32) -> Result<U256, ParseUnitsError> {
+    let error = |reason: &str| ParseUnitsError(format!("{input}: {reason}"));
+    let (whole, fraction) = input.trim().split_once('.').unwrap_or((input.trim(), ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(error("no digits"));
+    }
+    if !whole
+        .chars()
+        .chain(fraction.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return Err(error("not a positive decimal number"));
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(error(&format!("more than {decimals} decimals")));
+    }
+    let digits = format!("{whole}{fraction:0<width$}", width = decimals as usize);
+    match digits.trim_start_matches('0') {
+        "" => Ok(U256::zero()),
+        digits => U256::from_dec_str(digits).map_err(|_| error("too large")),
+    }
+}
+
+/// Formats base units as a decimal amount of coins, without trailing zeros.
+pub fn format_units(value: impl Into<U256>, decimals: u32) -> String {
+    let digits = format!(
+        "{:0>width$}",
+        value.into().to_string(),
+        width = decimals as usize + 1
+    );
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    match fraction.trim_end_matches('0') {
+        "" => whole.to_string(),
+        fraction => format!("{whole}.{fraction}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_units("1", 9), Ok(1_000_000_000.into()));
+        assert_eq!(parse_units("0.1", 9), Ok(100_000_000.into()));
+        assert_eq!(parse_units(".000000001", 9), Ok(1.into()));
+        assert_eq!(parse_units("2.50", 8), Ok(250_000_000.into()));
+        assert_eq!(parse_units("0", 9), Ok(0.into()));
+        assert!(parse_units("0.0000000001", 9).is_err());
+        assert!(parse_units("-1", 9).is_err());
+        assert!(parse_units("1e9", 9).is_err());
+        assert!(parse_units(".", 9).is_err());
+        assert_eq!(
+            parse_units("18446744073.709551616", 9),
+            Ok(U256::from(u64::MAX) + 1)
+        );
+        assert_eq!(
+            parse_units("1000000.000000000000000001", 18),
+            Ok(U256::exp10(24) + 1)
+        );
+        assert_eq!(parse_units(&U256::MAX.to_string(), 0), Ok(U256::MAX));
+        assert!(parse_units(&(U256::MAX.to_string() + "0"), 0).is_err());
+        assert!(parse_units(&U256::MAX.to_string(), 1).is_err());
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(format_units(1_000_000_000, 9), "1");
+        assert_eq!(format_units(100_000_000, 9), "0.1");
+        assert_eq!(format_units(1, 8), "0.00000001");
+        assert_eq!(format_units(u64::MAX, 9), "18446744073.709551615");
+        assert_eq!(format_units(0, 0), "0");
+        assert_eq!(
+            format_units(U256::exp10(24) + 1, 18),
+            "1000000.000000000000000001"
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::cmp::Reverse;
+use types::amount::Amount;
+use types::error::WalletError;
+use types::shared::Blockchain;
+
+/// Virtual size of the version, locktime, input and output counts and SegWit marker.
+pub(crate) const TX_OVERHEAD_VBYTES: u64 = 11;
+/// Virtual size of a P2WPKH input, including its witness.
+pub(crate) const P2WPKH_INPUT_VBYTES: u64 = 68;
+/// Virtual size of a P2WPKH output.
+pub(crate) const P2WPKH_OUTPUT_VBYTES: u64 = 31;
+/// Smallest P2WPKH output that relays by default; smaller change is left to the fee.
+pub(crate) const DUST_LIMIT: u64 = 294;
+
+/// Number of branches explored by branch and bound before falling back, as in Bitcoin Core.
+const BNB_MAX_TRIES: usize = 100_000;
+
+/// The UTXOs chosen to fund a payment.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Selection {
+    /// Indexes of the chosen UTXOs.
+    pub(crate) inputs: Vec<usize>,
+    pub(crate) fee: u64,
+    /// Zero if the transaction has no change output.
+    pub(crate) change: u64,
+}
+
+/// Chooses P2WPKH UTXOs among `values` to pay `target` at `fee_rate` sat/vB, for a transaction
+/// whose size without inputs and change is `base_vbytes`.
+///
+/// Branch and bound looks for a selection that needs no change output; if there is none, the
+/// largest UTXOs are used and the rest is sent back as change.
+pub(crate) fn select_coins(
+    values: &[u64],
+    target: u64,
+    fee_rate: f64,
+    base_vbytes: u64,
+) -> Result<Selection, WalletError> {
+    branch_and_bound(values, target, fee_rate, base_vbytes)
+        .or_else(|| largest_first(values, target, fee_rate, base_vbytes))
+        .ok_or_else(|| {
+            WalletError::insufficient_funds(
+                // The fee of a transaction spending every UTXO, without change.
+                Amount::native(
+                    Blockchain::Bitcoin,
+                    target
+                        + fee(
+                            base_vbytes + values.len() as u64 * P2WPKH_INPUT_VBYTES,
+                            fee_rate,
+                        ),
+                ),
+                Amount::native(Blockchain::Bitcoin, values.iter().sum::<u64>()),
+            )
+        })
+}
+
+fn fee(vbytes: u64, fee_rate: f64) -> u64 {
+    (vbytes as f64 * fee_rate).ceil() as u64
+}
+
+/// Depth-first search for a set of UTXOs whose effective value, net of its input fee, pays
+/// the target without exceeding it by more than the cost of creating and spending change.
+fn branch_and_bound(
+    values: &[u64],
+    target: u64,
+    fee_rate: f64,
+    base_vbytes: u64,
+) -> Option<Selection> {
+    let input_fee = fee(P2WPKH_INPUT_VBYTES, fee_rate);
+    let cost_of_change = fee(P2WPKH_OUTPUT_VBYTES + P2WPKH_INPUT_VBYTES, fee_rate);
+    let target = target + fee(base_vbytes, fee_rate);
+
+    let mut pool = values
+        .iter()
+        .enumerate()
+        .filter(|&(_, &value)| value > input_fee)
+        .map(|(i, &value)| (i, value - input_fee))
+        .collect::<Vec<_>>();
+    pool.sort_by_key(|&(_, value)| Reverse(value));
+
+    let mut selected: Vec<bool> = vec![];
+    let mut value = 0;
+    let mut available: u64 = pool.iter().map(|&(_, value)| value).sum();
+    let mut best: Option<(u64, Vec<bool>)> = None;
+    for _ in 0..BNB_MAX_TRIES {
+        let backtrack = if value + available < target || value > target + cost_of_change {
+            true
+        } else if value >= target {
+            let excess = value - target;
+            if best.as_ref().is_none_or(|(best, _)| excess < *best) {
+                best = Some((excess, selected.clone()));
+            }
+            true
+        } else {
+            false
+        };
+
+        if backtrack {
+            if best.as_ref().is_some_and(|(excess, _)| *excess == 0) {
+                break;
+            }
+            // Give back the UTXOs left out at the end of the branch, then leave out the last
+            // included one.
+            while selected.last() == Some(&false) {
+                selected.pop();
+                available += pool[selected.len()].1;
+            }
+            if selected.is_empty() {
+                break;
+            }
+            *selected.last_mut()? = false;
+            value -= pool[selected.len() - 1].1;
+        } else {
+            let next = pool[selected.len()].1;
+            available -= next;
+            selected.push(true);
+            value += next;
+        }
+    }
+
+    let (_, selected) = best?;
+    let inputs = selected
+        .iter()
+        .zip(&pool)
+        .filter(|(&included, _)| included)
+        .map(|(_, &(i, _))| i)
+        .collect::<Vec<_>>();
+    let total = inputs.iter().map(|&i| values[i]).sum::<u64>();
+    Some(Selection {
+        inputs,
+        fee: total - (target - fee(base_vbytes, fee_rate)),
+        change: 0,
+    })
+}
+
+/// Adds the largest UTXOs until they pay the target and the fee, with change if it is worth it.
+fn largest_first(
+    values: &[u64],
+    target: u64,
+    fee_rate: f64,
+    base_vbytes: u64,
+) -> Option<Selection> {
+    let mut order = (0..values.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| Reverse(values[i]));
+
+    let mut inputs = vec![];
+    let mut total = 0;
+    for i in order {
+        inputs.push(i);
+        total += values[i];
+        let vbytes = base_vbytes + inputs.len() as u64 * P2WPKH_INPUT_VBYTES;
+        let fee_with_change = fee(vbytes + P2WPKH_OUTPUT_VBYTES, fee_rate);
+        if total >= target + fee_with_change + DUST_LIMIT {
+            return Some(Selection {
+                change: total - target - fee_with_change,
+                fee: fee_with_change,
+                inputs,
+            });
+        }
+        if total >= target + fee(vbytes, fee_rate) {
+            return Some(Selection {
+                fee: total - target,
+                change: 0,
+                inputs,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A P2WPKH payment: overhead and a P2WPKH output.
+    const BASE_VBYTES: u64 = TX_OVERHEAD_VBYTES + P2WPKH_OUTPUT_VBYTES;
+
+    #[test]
+    fn branch_and_bound_finds_exact_match() {
+        // At 1 sat/vB, each input costs 68 sat and the base 42 sat.
+        let values = [100_000, 30_068, 20_068, 50_000];
+        let selection = select_coins(&values, 49_958, 1.0, BASE_VBYTES).unwrap();
+        assert_eq!(selection.change, 0);
+        let mut inputs = selection.inputs.clone();
+        inputs.sort();
+        assert_eq!(inputs, vec![1, 2]);
+        assert_eq!(selection.fee, 42 + 2 * 68);
+    }
+
+    #[test]
+    fn branch_and_bound_accepts_excess_below_cost_of_change() {
+        // Change would cost 99 sat to create and spend, so 50 sat of excess goes to the fee.
+        let selection = select_coins(&[10_160, 500_000], 10_000, 1.0, BASE_VBYTES).unwrap();
+        assert_eq!(
+            selection,
+            Selection {
+                inputs: vec![0],
+                fee: 160,
+                change: 0
+            }
+        );
+    }
+
+    #[test]
+    fn largest_first_with_change() {
+        let selection = select_coins(&[20_000, 70_000, 50_000], 100_000, 2.0, BASE_VBYTES).unwrap();
+        let fee = (BASE_VBYTES + 2 * P2WPKH_INPUT_VBYTES + P2WPKH_OUTPUT_VBYTES) * 2;
+        assert_eq!(
+            selection,
+            Selection {
+                inputs: vec![1, 2],
+                fee,
+                change: 120_000 - 100_000 - fee
+            }
+        );
+    }
+
+    #[test]
+    fn largest_first_drops_dust_change() {
+        let selection = select_coins(&[10_300], 10_000, 1.0, BASE_VBYTES).unwrap();
+        assert_eq!(
+            selection,
+            Selection {
+                inputs: vec![0],
+                fee: 300,
+                change: 0
+            }
+        );
+    }
+
+    #[test]
+    fn insufficient_funds() {
+        assert_eq!(
+            select_coins(&[5_000, 5_000], 10_000, 1.0, BASE_VBYTES),
+            Err(WalletError::insufficient_funds(
+                Amount::native(
+                    Blockchain::Bitcoin,
+                    10_000 + BASE_VBYTES + 2 * P2WPKH_INPUT_VBYTES
+                ),
+                Amount::native(Blockchain::Bitcoin, 10_000u64),
+            ))
+        );
+        assert!(select_coins(&[], 1, 1.0, BASE_VBYTES).is_err());
+    }
+}
@@ -0,0 +1,348 @@
+use crate::keystore::{data_dir, DATA_DIR_ENV};
+use api::endpoint::{ApiConfig, Endpoint};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use types::error::WalletError;
+use types::shared::{Address, Blockchain, Net};
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// User settings, kept in `config.toml` in the config directory so that they can be edited by
+/// hand:
+///
+/// ```toml
+/// [[tokens]]
+/// net = "main"
+/// blockchain = "ethereum"
+/// address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+/// symbol = "USDC"
+///
+/// [[networks]]
+/// name = "my-localnet"
+/// net = "local"
+///
+/// [[endpoints]]
+/// network = "my-localnet"
+/// blockchain = "solana"
+/// url = "http://localhost:18899"
+///
+/// [[endpoints]]
+/// network = "main"
+/// blockchain = "ethereum"
+/// url = "https://eth-mainnet.example.com/v2"
+/// headers = { Authorization = "Bearer <api-key>" }
+/// explorer = "https://eth.blockscout.com/api"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    /// The tokens whose balances are shown. Solana lists the token accounts of a wallet by
+    /// itself, Ethereum only knows the balances of the ERC-20 contracts listed here.
+    #[serde(default)]
+    pub tokens: Vec<Token>,
+    /// Named networks besides `main`, `test`, `dev` and `local`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<Network>,
+    /// Nodes and API servers used instead of the public ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub endpoints: Vec<NetworkEndpoint>,
+}
+
+/// A custom network, e.g. a private cluster or a local node on another port, reached through
+/// the endpoints configured for its name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Network {
+    pub name: String,
+    /// The built-in network whose address formats, derivation paths and tokens it uses.
+    pub net: Net,
+}
+
+/// The endpoint of a blockchain on a network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkEndpoint {
+    /// `main`, `test`, `dev`, `local` or the name of a custom network.
+    pub network: String,
+    pub blockchain: Blockchain,
+    #[serde(flatten)]
+    pub endpoint: Endpoint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Token {
+    pub net: Net,
+    pub blockchain: Blockchain,
+    /// The contract (Ethereum) or mint (Solana) address.
+    pub address: Address,
+    /// Only a reminder for the reader of the file, the symbol is read from the token itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+impl Default for Config {
+    /// Well-known stablecoins on Ethereum mainnet and Sepolia.
+    fn default() -> Self {
+        let token = |net, address: &str, symbol: &str| Token {
+            net,
+            blockchain: Blockchain::Ethereum,
+            address: address.to_string(),
+            symbol: Some(symbol.to_string()),
+        };
+        Self {
+            tokens: vec![
+                token(
+                    Net::Main,
+                    "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                    "USDC",
+                ),
+                token(
+                    Net::Main,
+                    "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+                    "USDT",
+                ),
+                token(
+                    Net::Main,
+                    "0x6B175474E89094C44Da98b954EedeAC495271d0F",
+                    "DAI",
+                ),
+                token(
+                    Net::Test,
+                    "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238",
+                    "USDC",
+                ),
+            ],
+            networks: vec![],
+            endpoints: vec![],
+        }
+    }
+}
+
+impl Config {
+    /// `config.toml` in `$WALLET_DATA_DIR` if set, in the platform config directory otherwise
+    /// (e.g. `~/.config/crypto-wallet` on Linux). A config left in the data directory by
+    /// earlier versions is moved there, or still used if it can't be moved.
+    pub fn default_path() -> PathBuf {
+        let legacy = data_dir().join(CONFIG_FILE);
+        match dirs::config_dir() {
+            Some(dir) if std::env::var_os(DATA_DIR_ENV).is_none() => {
+                migrate(&legacy, dir.join("crypto-wallet").join(CONFIG_FILE))
+            }
+            _ => legacy,
+        }
+    }
+
+    /// Reads the config at `path`, or the default one if the file doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self, WalletError> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| WalletError::Storage(format!("Invalid config {}: {e}", path.display())))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), WalletError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self).map_err(WalletError::storage)?)?;
+        Ok(())
+    }
+
+    /// The addresses of the tokens listed for a blockchain on a network.
+    pub fn tokens(&self, net: Net, blockchain: Blockchain) -> Vec<Address> {
+        self.tokens
+            .iter()
+            .filter(|token| token.net == net && token.blockchain == blockchain)
+            .map(|token| token.address.clone())
+            .collect()
+    }
+
+    /// The built-in network of `network`, the name of a built-in or a custom network.
+    pub fn net(&self, network: &str) -> Result<Net, WalletError> {
+        network
+            .parse()
+            .ok()
+            .or_else(|| {
+                self.networks
+                    .iter()
+                    .find(|custom| custom.name.eq_ignore_ascii_case(network))
+                    .map(|custom| custom.net)
+            })
+            .ok_or_else(|| WalletError::InvalidInput(format!("Unknown network {network}")))
+    }
+
+    /// The endpoints configured for `network`, with the default timeout.
+    pub fn api_config(&self, network: &str) -> ApiConfig {
+        ApiConfig {
+            endpoints: self
+                .endpoints
+                .iter()
+                .filter(|endpoint| endpoint.network.eq_ignore_ascii_case(network))
+                .map(|endpoint| (endpoint.blockchain, endpoint.endpoint.clone()))
+                .collect(),
+            ..ApiConfig::default()
+        }
+    }
+
+    /// Adds a token to the list, unless it is already there. Returns whether it was added.
+    pub fn add_token(&mut self, token: Token) -> bool {
+        let listed = self.tokens.iter().any(|listed| {
+            listed.net == token.net
+                && listed.blockchain == token.blockchain
+                && listed.address.eq_ignore_ascii_case(&token.address)
+        });
+        if !listed {
+            self.tokens.push(token);
+        }
+        !listed
+    }
+}
+
+/// Moves the config at `old` to `new` unless there's one there already, and returns the path
+/// of the config.
+fn migrate(old: &Path, new: PathBuf) -> PathBuf {
+    if new.exists() || !old.exists() {
+        return new;
+    }
+    let moved = new
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::rename(old, &new));
+    match moved {
+        Ok(()) => new,
+        Err(_) => old.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_when_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::load(&dir.path().join("config.toml")).unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.tokens(Net::Main, Blockchain::Ethereum).len(), 3);
+        assert!(config.tokens(Net::Local, Blockchain::Ethereum).is_empty());
+    }
+
+    #[test]
+    fn edited_by_hand() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            r#"
+[[tokens]]
+net = "local"
+blockchain = "ethereum"
+address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+"#,
+        )
+        .unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(
+            config.tokens(Net::Local, Blockchain::Ethereum),
+            vec!["0x5FbDB2315678afecb367f032d93F642f64180aa3"]
+        );
+        assert!(config.tokens(Net::Main, Blockchain::Ethereum).is_empty());
+
+        fs::write(&path, "tokens = 1").unwrap();
+        assert!(Config::load(&path).is_err());
+    }
+
+    #[test]
+    fn networks_and_endpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            r#"
+[[networks]]
+name = "my-localnet"
+net = "local"
+
+[[endpoints]]
+network = "my-localnet"
+blockchain = "solana"
+url = "http://localhost:18899"
+
+[[endpoints]]
+network = "main"
+blockchain = "ethereum"
+url = "https://eth-mainnet.example.com/v2"
+headers = { Authorization = "Bearer secret" }
+explorer = "https://explorer.example.com/api"
+"#,
+        )
+        .unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.net("main").unwrap(), Net::Main);
+        assert_eq!(config.net("my-localnet").unwrap(), Net::Local);
+        assert_eq!(config.net("My-Localnet").unwrap(), Net::Local);
+        assert!(matches!(
+            config.net("staging"),
+            Err(WalletError::InvalidInput(_))
+        ));
+
+        let local = config.api_config("my-localnet");
+        assert_eq!(
+            local.endpoints[&Blockchain::Solana],
+            Endpoint::new("http://localhost:18899")
+        );
+        assert_eq!(local.endpoints.len(), 1);
+        assert_eq!(
+            config.api_config("main").endpoints[&Blockchain::Ethereum],
+            Endpoint::new("https://eth-mainnet.example.com/v2")
+                .with_header("Authorization", "Bearer secret")
+                .with_explorer("https://explorer.example.com/api")
+        );
+        assert!(config.api_config("local").endpoints.is_empty());
+
+        config.save(&path).unwrap();
+        assert_eq!(Config::load(&path).unwrap(), config);
+    }
+
+    #[test]
+    fn migrate_from_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("data").join(CONFIG_FILE);
+        let new = dir.path().join("config").join(CONFIG_FILE);
+        assert_eq!(migrate(&old, new.clone()), new);
+
+        let mut config = Config::default();
+        config.networks.push(Network {
+            name: "staging".to_string(),
+            net: Net::Dev,
+        });
+        config.save(&old).unwrap();
+        assert_eq!(migrate(&old, new.clone()), new);
+        assert!(!old.exists());
+        assert_eq!(Config::load(&new).unwrap(), config);
+
+        // A config already in the config directory wins.
+        Config::default().save(&old).unwrap();
+        assert_eq!(migrate(&old, new.clone()), new);
+        assert_eq!(Config::load(&new).unwrap(), config);
+    }
+
+    #[test]
+    fn add_token_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data").join("config.toml");
+        let mut config = Config::default();
+        let token = Token {
+            net: Net::Local,
+            blockchain: Blockchain::Ethereum,
+            address: "0x5FbDB2315678afecb367f032d93F642f64180aa3".to_string(),
+            symbol: None,
+        };
+        assert!(config.add_token(token.clone()));
+        assert!(!config.add_token(Token {
+            address: token.address.to_lowercase(),
+            ..token
+        }));
+        config.save(&path).unwrap();
+        assert_eq!(Config::load(&path).unwrap(), config);
+    }
+}
@@ -0,0 +1,459 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use keypair::account::AddressIndex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use types::error::WalletError;
+use types::shared::{Blockchain, Net};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Version of the keystore file format.
+const VERSION: u32 = 1;
+
+/// Environment variable that overrides the default data directory.
+pub const DATA_DIR_ENV: &str = "WALLET_DATA_DIR";
+
+const CIPHER: &str = "aes-256-gcm";
+const KDF: &str = "scrypt";
+const KEY_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 32;
+const DEFAULT_SCRYPT_LOG_N: u8 = 17;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// `$WALLET_DATA_DIR` if set, the platform data directory otherwise
+/// (e.g. `~/.local/share/crypto-wallet` on Linux).
+pub fn data_dir() -> PathBuf {
+    std::env::var_os(DATA_DIR_ENV)
+        .map(PathBuf::from)
+        .or_else(|| dirs::data_dir().map(|dir| dir.join("crypto-wallet")))
+        .unwrap_or_else(|| PathBuf::from(".crypto-wallet"))
+}
+
+/// A directory of password-encrypted wallet files, one `<name>.json` file per wallet.
+///
+/// The file format follows the Ethereum V3 keystore: the seed phrase and passphrase are
+/// encrypted with AES-256-GCM under a key derived from the password with scrypt, while the
+/// parameters needed to rebuild the wallet (network, language, derivation) stay readable.
+pub struct Keystore {
+    dir: PathBuf,
+    scrypt_log_n: u8,
+}
+
+impl Default for Keystore {
+    fn default() -> Self {
+        Keystore::new(Keystore::default_dir())
+    }
+}
+
+impl Keystore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            scrypt_log_n: DEFAULT_SCRYPT_LOG_N,
+        }
+    }
+
+    /// `keystore` in the data directory, e.g. `~/.local/share/crypto-wallet/keystore` on Linux.
+    pub fn default_dir() -> PathBuf {
+        data_dir().join("keystore")
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    /// Lists the names of the stored wallets in alphabetical order.
+    pub fn list(&self) -> Result<Vec<String>, WalletError> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut names = fs::read_dir(&self.dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| path.file_stem()?.to_str().map(str::to_string))
+            .collect::<Vec<_>>();
+        names.sort();
+        Ok(names)
+    }
+
+    pub(crate) fn save(&self, wallet: &StoredWallet, password: &str) -> Result<(), WalletError> {
+        let path = self.path(&wallet.name)?;
+        if path.exists() {
+            return Err(WalletError::WalletExists(wallet.name.clone()));
+        }
+
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        let kdfparams = KdfParams {
+            dklen: KEY_LENGTH,
+            n: 1 << self.scrypt_log_n,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: hex::encode(salt),
+        };
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(
+            kdfparams.derive_key(password)?.as_slice(),
+        ));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, wallet.secrets.to_json()?.as_slice())
+            .map_err(|_| WalletError::crypto("Failed to encrypt the wallet"))?;
+
+        let file = KeystoreFile {
+            version: VERSION,
+            name: wallet.name.clone(),
+            net: wallet.net,
+            language_code: wallet.language_code.clone(),
+            bitcoin_address_type: wallet.bitcoin_address_type.clone(),
+            solana_derivation: wallet.solana_derivation.clone(),
+            opened: opened_addresses(&wallet.opened),
+            crypto: Crypto {
+                cipher: CIPHER.to_string(),
+                cipherparams: CipherParams {
+                    nonce: hex::encode(nonce),
+                },
+                ciphertext: hex::encode(ciphertext),
+                kdf: KDF.to_string(),
+                kdfparams,
+            },
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        write_file(&path, &file, true)
+    }
+
+    /// Replaces the opened addresses of a saved wallet. They are not secret, so the wallet
+    /// doesn't need to be decrypted.
+    pub(crate) fn save_opened(
+        &self,
+        name: &str,
+        opened: &[(Blockchain, AddressIndex)],
+    ) -> Result<(), WalletError> {
+        let path = self.path(name)?;
+        let mut file = self.read(name, &path)?;
+        file.opened = opened_addresses(opened);
+
+        // Write to a temporary file first so that a failure never leaves a truncated keystore.
+        let tmp = path.with_extension("json.tmp");
+        write_file(&tmp, &file, false)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    pub(crate) fn load(&self, name: &str, password: &str) -> Result<StoredWallet, WalletError> {
+        let file = self.read(name, &self.path(name)?)?;
+        if file.crypto.cipher != CIPHER || file.crypto.kdf != KDF {
+            return Err(WalletError::Storage(format!(
+                "Unsupported keystore encryption {}/{}",
+                file.crypto.kdf, file.crypto.cipher
+            )));
+        }
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(
+            file.crypto.kdfparams.derive_key(password)?.as_slice(),
+        ));
+        let nonce = hex::decode(&file.crypto.cipherparams.nonce).map_err(WalletError::storage)?;
+        if nonce.len() != 12 {
+            return Err(WalletError::storage("Invalid keystore nonce"));
+        }
+        let ciphertext = hex::decode(&file.crypto.ciphertext).map_err(WalletError::storage)?;
+        // AES-GCM can't tell a wrong key from a corrupted ciphertext.
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+                .map_err(|_| WalletError::WrongPassword)?,
+        );
+
+        Ok(StoredWallet {
+            name: file.name,
+            net: file.net,
+            language_code: file.language_code,
+            bitcoin_address_type: file.bitcoin_address_type,
+            solana_derivation: file.solana_derivation,
+            opened: file
+                .opened
+                .into_iter()
+                .map(|opened| (opened.blockchain, opened.index))
+                .collect(),
+            secrets: serde_json::from_slice(&plaintext).map_err(WalletError::storage)?,
+        })
+    }
+
+    fn read(&self, name: &str, path: &Path) -> Result<KeystoreFile, WalletError> {
+        if !path.exists() {
+            return Err(WalletError::WalletNotFound(name.to_string()));
+        }
+        let file: KeystoreFile =
+            serde_json::from_slice(&fs::read(path)?).map_err(WalletError::storage)?;
+        if file.version != VERSION {
+            return Err(WalletError::Storage(format!(
+                "Unsupported keystore version {}",
+                file.version
+            )));
+        }
+        Ok(file)
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, WalletError> {
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(WalletError::Storage(format!(
+                "Invalid wallet name '{name}': use letters, digits, '-', '_' and '.'"
+            )));
+        }
+        Ok(self.dir.join(format!("{name}.json")))
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_scrypt_log_n(mut self, scrypt_log_n: u8) -> Self {
+        self.scrypt_log_n = scrypt_log_n;
+        self
+    }
+}
+
+/// Everything needed to rebuild a wallet.
+pub(crate) struct StoredWallet {
+    pub(crate) name: String,
+    pub(crate) net: Net,
+    pub(crate) language_code: String,
+    pub(crate) bitcoin_address_type: String,
+    pub(crate) solana_derivation: String,
+    pub(crate) opened: Vec<(Blockchain, AddressIndex)>,
+    pub(crate) secrets: Secrets,
+}
+
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub(crate) struct Secrets {
+    pub(crate) seed_phrase: String,
+    pub(crate) passphrase: String,
+}
+
+impl Secrets {
+    /// The JSON of the secrets, in a buffer sized up front so that it never leaves copies.
+    fn to_json(&self) -> Result<Zeroizing<Vec<u8>>, WalletError> {
+        // `{"seed_phrase":"","passphrase":""}`, where a character is escaped in at most 6 bytes.
+        let capacity = 34 + 6 * (self.seed_phrase.len() + self.passphrase.len());
+        let mut json = Zeroizing::new(Vec::with_capacity(capacity));
+        serde_json::to_writer(&mut *json, self).map_err(WalletError::storage)?;
+        Ok(json)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeystoreFile {
+    version: u32,
+    name: String,
+    net: Net,
+    language_code: String,
+    #[serde(default = "default_bitcoin_address_type")]
+    bitcoin_address_type: String,
+    solana_derivation: String,
+    #[serde(default)]
+    opened: Vec<OpenedAddress>,
+    crypto: Crypto,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpenedAddress {
+    blockchain: Blockchain,
+    #[serde(flatten)]
+    index: AddressIndex,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Crypto {
+    cipher: String,
+    cipherparams: CipherParams,
+    ciphertext: String,
+    kdf: String,
+    kdfparams: KdfParams,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CipherParams {
+    nonce: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KdfParams {
+    dklen: usize,
+    n: u64,
+    r: u32,
+    p: u32,
+    salt: String,
+}
+
+impl KdfParams {
+    fn derive_key(&self, password: &str) -> Result<Zeroizing<[u8; KEY_LENGTH]>, WalletError> {
+        if !self.n.is_power_of_two() || self.dklen != KEY_LENGTH {
+            return Err(WalletError::storage("Invalid keystore scrypt parameters"));
+        }
+        let params = scrypt::Params::new(self.n.ilog2() as u8, self.r, self.p, self.dklen)
+            .map_err(WalletError::storage)?;
+        let mut key = Zeroizing::new([0; KEY_LENGTH]);
+        scrypt::scrypt(
+            password.as_bytes(),
+            &hex::decode(&self.salt).map_err(WalletError::storage)?,
+            &params,
+            key.as_mut_slice(),
+        )
+        .map_err(WalletError::crypto)?;
+        Ok(key)
+    }
+}
+
+/// Keystores written before the address type was configurable hold P2WPKH wallets.
+fn default_bitcoin_address_type() -> String {
+    "p2wpkh".to_string()
+}
+
+fn opened_addresses(opened: &[(Blockchain, AddressIndex)]) -> Vec<OpenedAddress> {
+    opened
+        .iter()
+        .map(|&(blockchain, index)| OpenedAddress { blockchain, index })
+        .collect()
+}
+
+fn write_file(path: &Path, file: &KeystoreFile, create_new: bool) -> Result<(), WalletError> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if create_new {
+        options.create_new(true);
+    } else {
+        options.create(true).truncate(true);
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(
+        serde_json::to_string_pretty(file)
+            .map_err(WalletError::storage)?
+            .as_bytes(),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keystore(dir: &tempfile::TempDir) -> Keystore {
+        Keystore::new(dir.path()).with_scrypt_log_n(4)
+    }
+
+    fn stored_wallet(name: &str) -> StoredWallet {
+        StoredWallet {
+            name: name.to_string(),
+            net: Net::Test,
+            language_code: "en".to_string(),
+            bitcoin_address_type: "p2tr".to_string(),
+            solana_derivation: "legacy".to_string(),
+            opened: vec![(Blockchain::Bitcoin, AddressIndex::default())],
+            secrets: Secrets {
+                seed_phrase:
+                    "brisk fossil brisk dream dry sing lottery mountain sound void refuse pumpkin"
+                        .to_string(),
+                passphrase: "paSword!".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = keystore(&dir);
+        keystore.save(&stored_wallet("main"), "secret").unwrap();
+
+        let content = fs::read_to_string(dir.path().join("main.json")).unwrap();
+        assert!(!content.contains("brisk"));
+        assert!(!content.contains("paSword!"));
+
+        let loaded = keystore.load("main", "secret").unwrap();
+        assert_eq!(loaded.name, "main");
+        assert_eq!(loaded.net, Net::Test);
+        assert_eq!(loaded.solana_derivation, "legacy");
+        assert_eq!(
+            loaded.secrets.seed_phrase,
+            stored_wallet("main").secrets.seed_phrase
+        );
+        assert_eq!(loaded.secrets.passphrase, "paSword!");
+    }
+
+    #[test]
+    fn save_opened() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = keystore(&dir);
+        keystore.save(&stored_wallet("main"), "secret").unwrap();
+        let opened = vec![
+            (Blockchain::Bitcoin, AddressIndex::default()),
+            (Blockchain::Ethereum, AddressIndex::new(1, false, 5)),
+        ];
+        keystore.save_opened("main", &opened).unwrap();
+
+        let loaded = keystore.load("main", "secret").unwrap();
+        assert_eq!(loaded.opened, opened);
+        assert_eq!(loaded.secrets.passphrase, "paSword!");
+    }
+
+    #[test]
+    fn wrong_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = keystore(&dir);
+        keystore.save(&stored_wallet("main"), "secret").unwrap();
+        assert!(matches!(
+            keystore.load("main", "Secret"),
+            Err(WalletError::WrongPassword)
+        ));
+    }
+
+    #[test]
+    fn no_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = keystore(&dir);
+        keystore.save(&stored_wallet("main"), "secret").unwrap();
+        assert_eq!(
+            keystore.save(&stored_wallet("main"), "other").unwrap_err(),
+            WalletError::WalletExists("main".to_string())
+        );
+    }
+
+    #[test]
+    fn list() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = keystore(&dir);
+        assert!(keystore.list().unwrap().is_empty());
+        keystore.save(&stored_wallet("savings"), "secret").unwrap();
+        keystore.save(&stored_wallet("daily"), "secret").unwrap();
+        assert_eq!(keystore.list().unwrap(), vec!["daily", "savings"]);
+    }
+
+    #[test]
+    fn invalid_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = keystore(&dir);
+        assert!(keystore.save(&stored_wallet("../main"), "secret").is_err());
+        assert!(keystore.load("", "secret").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        keystore(&dir)
+            .save(&stored_wallet("main"), "secret")
+            .unwrap();
+        let metadata = fs::metadata(dir.path().join("main.json")).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+    }
+}
@@ -1 +1,6 @@
+mod coin_selection;
+pub mod config;
+pub mod keystore;
+pub mod portfolio;
+mod psbt;
 pub mod wallet;
@@ -0,0 +1,117 @@
+use keypair::account::AddressIndex;
+use types::amount::Amount;
+use types::error::WalletError;
+use types::shared::{Address, Blockchain};
+
+/// The native balance of one address of a wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressBalance {
+    pub blockchain: Blockchain,
+    pub index: AddressIndex,
+    pub address: Address,
+    /// The balance, or why it couldn't be queried.
+    pub balance: Result<Amount, WalletError>,
+}
+
+/// The native balances of the addresses of a wallet, ordered by blockchain and index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Portfolio {
+    pub addresses: Vec<AddressBalance>,
+}
+
+impl Portfolio {
+    /// The blockchains that have addresses in the portfolio.
+    pub fn blockchains(&self) -> Vec<Blockchain> {
+        let mut blockchains = self
+            .addresses
+            .iter()
+            .map(|address| address.blockchain)
+            .collect::<Vec<_>>();
+        blockchains.dedup();
+        blockchains
+    }
+
+    pub fn addresses(&self, blockchain: Blockchain) -> impl Iterator<Item = &AddressBalance> {
+        self.addresses
+            .iter()
+            .filter(move |address| address.blockchain == blockchain)
+    }
+
+    /// The sum of the balances of a blockchain that could be queried.
+    pub fn total(&self, blockchain: Blockchain) -> Result<Amount, WalletError> {
+        self.addresses(blockchain)
+            .filter_map(|address| address.balance.as_ref().ok())
+            .try_fold(Amount::native(blockchain, 0), |total, balance| {
+                total.checked_add(balance)
+            })
+            .ok_or_else(|| {
+                WalletError::InvalidAmount(format!("The total {blockchain} balance overflows"))
+            })
+    }
+
+    /// Whether the balance of some address of a blockchain couldn't be queried, so that its
+    /// total is a lower bound.
+    pub fn is_incomplete(&self, blockchain: Blockchain) -> bool {
+        self.addresses(blockchain)
+            .any(|address| address.balance.is_err())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balance(
+        blockchain: Blockchain,
+        index: u32,
+        balance: Result<u64, WalletError>,
+    ) -> AddressBalance {
+        AddressBalance {
+            blockchain,
+            index: AddressIndex::new(0, false, index),
+            address: format!("{blockchain}-{index}"),
+            balance: balance.map(|units| Amount::native(blockchain, units)),
+        }
+    }
+
+    #[test]
+    fn totals() {
+        let portfolio = Portfolio {
+            addresses: vec![
+                balance(Blockchain::Bitcoin, 0, Ok(150_000)),
+                balance(Blockchain::Bitcoin, 1, Ok(50_000)),
+                balance(Blockchain::Ethereum, 0, Ok(u64::MAX)),
+                balance(Blockchain::Ethereum, 1, Ok(1)),
+                balance(Blockchain::Solana, 0, Ok(1_500_000_000)),
+                balance(
+                    Blockchain::Solana,
+                    1,
+                    Err(WalletError::Network("unreachable".to_string())),
+                ),
+            ],
+        };
+        assert_eq!(
+            portfolio.blockchains(),
+            [
+                Blockchain::Bitcoin,
+                Blockchain::Ethereum,
+                Blockchain::Solana
+            ]
+        );
+        assert_eq!(
+            portfolio.total(Blockchain::Bitcoin).unwrap().to_string(),
+            "0.002 BTC"
+        );
+        assert!(!portfolio.is_incomplete(Blockchain::Bitcoin));
+        // More wei than fit in 64 bits.
+        assert_eq!(
+            portfolio.total(Blockchain::Ethereum).unwrap().to_string(),
+            "18.446744073709551616 ETH"
+        );
+        assert_eq!(
+            portfolio.total(Blockchain::Solana).unwrap().to_string(),
+            "1.5 SOL"
+        );
+        assert!(portfolio.is_incomplete(Blockchain::Solana));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::coin_selection::{select_coins, DUST_LIMIT, TX_OVERHEAD_VBYTES};
+use api::client::Utxo;
+use bitcoin::absolute::LockTime;
+use bitcoin::psbt::Psbt;
+use bitcoin::transaction::Version;
+use bitcoin::{
+    Amount, CompressedPublicKey, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut,
+    Txid, Witness,
+};
+use keypair::account::{Account, AddressIndex};
+use keypair::BitcoinAddressType;
+use std::str::FromStr;
+use types::error::WalletError;
+
+/// An unspent output of one of the wallet's addresses.
+pub(crate) struct OwnedUtxo {
+    pub(crate) utxo: Utxo,
+    pub(crate) index: AddressIndex,
+}
+
+/// Builds an unsigned BIP-174 PSBT that pays `amount` satoshis to `to` at `fee_rate` sat/vB,
+/// funded by `utxos`, with any change sent to the address at `change`.
+pub(crate) fn build_psbt(
+    account: &Account,
+    utxos: &[OwnedUtxo],
+    to: &str,
+    amount: u64,
+    fee_rate: f64,
+    change: AddressIndex,
+) -> Result<Psbt, WalletError> {
+    if account.derivation().bitcoin != BitcoinAddressType::P2wpkh {
+        return Err(WalletError::UnsupportedBlockchain(
+            "Only wallets with P2WPKH addresses can send Bitcoin".to_string(),
+        ));
+    }
+    if amount < DUST_LIMIT {
+        return Err(WalletError::InvalidAmount(format!(
+            "Cannot send less than {DUST_LIMIT} sat"
+        )));
+    }
+    let recipient = bitcoin::Address::from_str(to)
+        .map_err(WalletError::invalid_address)?
+        .require_network(Network::from(account.net()))
+        .map_err(WalletError::invalid_address)?
+        .script_pubkey();
+    // The recipient output: value, script length and script.
+    let base_vbytes = TX_OVERHEAD_VBYTES + 9 + recipient.len() as u64;
+    let values = utxos.iter().map(|u| u.utxo.value).collect::<Vec<_>>();
+    let selection = select_coins(&values, amount, fee_rate, base_vbytes)?;
+    let inputs = selection
+        .inputs
+        .iter()
+        .map(|&i| &utxos[i])
+        .collect::<Vec<_>>();
+
+    let mut outputs = vec![TxOut {
+        value: Amount::from_sat(amount),
+        script_pubkey: recipient,
+    }];
+    if selection.change > 0 {
+        outputs.push(TxOut {
+            value: Amount::from_sat(selection.change),
+            script_pubkey: p2wpkh_script(account, change)?,
+        });
+    }
+    let transaction = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: inputs
+            .iter()
+            .map(|owned| {
+                Ok(TxIn {
+                    previous_output: OutPoint::new(
+                        Txid::from_str(&owned.utxo.txid).map_err(WalletError::network)?,
+                        owned.utxo.vout,
+                    ),
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+            })
+            .collect::<Result<_, WalletError>>()?,
+        output: outputs,
+    };
+
+    let mut psbt = Psbt::from_unsigned_tx(transaction).map_err(WalletError::crypto)?;
+    for (input, owned) in psbt.inputs.iter_mut().zip(&inputs) {
+        let (public_key, origin) = account.bitcoin_key_origin(owned.index)?;
+        input.witness_utxo = Some(TxOut {
+            value: Amount::from_sat(owned.utxo.value),
+            script_pubkey: p2wpkh_script(account, owned.index)?,
+        });
+        input.bip32_derivation.insert(public_key, origin);
+    }
+    if selection.change > 0 {
+        let (public_key, origin) = account.bitcoin_key_origin(change)?;
+        psbt.outputs[1].bip32_derivation.insert(public_key, origin);
+    }
+    Ok(psbt)
+}
+
+fn p2wpkh_script(account: &Account, index: AddressIndex) -> Result<ScriptBuf, WalletError> {
+    let (public_key, _) = account.bitcoin_key_origin(index)?;
+    Ok(ScriptBuf::new_p2wpkh(
+        &CompressedPublicKey(public_key).wpubkey_hash(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::{Message, Secp256k1};
+    use bitcoin::sighash::{EcdsaSighashType, SighashCache};
+    use keypair::Derivation;
+    use types::shared::{Blockchain, Net};
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn account() -> Account {
+        Account::restore_from_seed(Net::Local, "en", PHRASE, "", &Derivation::default()).unwrap()
+    }
+
+    fn utxo(txid: &str, value: u64, index: AddressIndex) -> OwnedUtxo {
+        OwnedUtxo {
+            utxo: Utxo {
+                txid: txid.repeat(64),
+                vout: 0,
+                value,
+                confirmed: true,
+            },
+            index,
+        }
+    }
+
+    #[test]
+    fn build_sign_and_finalize() {
+        let account = account();
+        let to = account
+            .derive_address(Blockchain::Bitcoin, AddressIndex::new(1, false, 0))
+            .unwrap();
+        let change = AddressIndex::new(0, true, 0);
+        let utxos = [
+            utxo("a", 60_000, AddressIndex::new(0, false, 0)),
+            utxo("b", 70_000, AddressIndex::new(0, false, 3)),
+            utxo("c", 1_000, AddressIndex::new(0, true, 1)),
+        ];
+
+        let mut psbt = build_psbt(&account, &utxos, &to, 100_000, 2.0, change).unwrap();
+        assert_eq!(psbt.unsigned_tx.input.len(), 2);
+        assert_eq!(psbt.unsigned_tx.output.len(), 2);
+        assert_eq!(
+            psbt.unsigned_tx.output[1].script_pubkey,
+            p2wpkh_script(&account, change).unwrap()
+        );
+        assert_eq!(psbt.outputs[1].bip32_derivation.len(), 1);
+        let fee = psbt.fee().unwrap().to_sat();
+
+        account.sign_bitcoin_psbt(&mut psbt).unwrap();
+        let transaction = psbt.clone().extract_tx().unwrap();
+        // The size estimate must not undershoot the real size.
+        assert!(fee >= transaction.vsize() as u64 * 2);
+
+        let secp = Secp256k1::verification_only();
+        let mut cache = SighashCache::new(&transaction);
+        for (i, input) in psbt.inputs.iter().enumerate() {
+            let utxo = input.witness_utxo.as_ref().unwrap();
+            let witness = input.final_script_witness.as_ref().unwrap();
+            let public_key = bitcoin::PublicKey::from_slice(&witness[1]).unwrap();
+            let signature = bitcoin::ecdsa::Signature::from_slice(&witness[0]).unwrap();
+            assert_eq!(signature.sighash_type, EcdsaSighashType::All);
+            let sighash = cache
+                .p2wpkh_signature_hash(i, &utxo.script_pubkey, utxo.value, EcdsaSighashType::All)
+                .unwrap();
+            secp.verify_ecdsa(
+                &Message::from(sighash),
+                &signature.signature,
+                &public_key.inner,
+            )
+            .unwrap();
+            assert!(input.bip32_derivation.is_empty());
+        }
+    }
+
+    #[test]
+    fn wrong_network() {
+        let account = account();
+        let utxos = [utxo("a", 60_000, AddressIndex::default())];
+        assert!(build_psbt(
+            &account,
+            &utxos,
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            10_000,
+            1.0,
+            AddressIndex::new(0, true, 0)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn only_p2wpkh_wallets() {
+        let account = Account::restore_from_seed(
+            Net::Local,
+            "en",
+            PHRASE,
+            "",
+            &Derivation {
+                bitcoin: BitcoinAddressType::P2tr,
+                ..Derivation::default()
+            },
+        )
+        .unwrap();
+        let to = account
+            .derive_address(Blockchain::Bitcoin, AddressIndex::new(1, false, 0))
+            .unwrap();
+        let utxos = [utxo("a", 60_000, AddressIndex::default())];
+        assert!(build_psbt(&account, &utxos, &to, 10_000, 1.0, AddressIndex::default()).is_err());
+    }
+}
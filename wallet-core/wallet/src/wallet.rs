@@ -1,14 +1,50 @@
-use keypair::account::Account;
+use crate::keystore::{Keystore, Secrets, StoredWallet};
+use crate::portfolio::{AddressBalance, Portfolio};
+use crate::psbt::{build_psbt, OwnedUtxo};
+use api::client::{Api, TokenBalance, TransactionSummary};
+use futures::future::try_join_all;
+use keypair::account::{Account, AddressIndex};
+use keypair::watch::WatchOnlyAccount;
+use keypair::{write_secrets, BitcoinAddressType, Derivation};
+use std::fmt;
 use std::fmt::Formatter;
-use std::{error, fmt};
+use std::fs;
+use std::io::Write as _;
+use std::path::Path;
+use types::amount::Amount;
 use types::constants::{TEXT_STYLE_BOLD, TEXT_STYLE_RESET, TEXT_STYLE_UNDERLINED};
-use types::shared::Net;
+use types::error::WalletError;
+use types::shared::{Address, Blockchain, Commitment, Net};
+use zeroize::Zeroizing;
+
+/// Gap limit of the search for Bitcoin funds, that of BIP-44: the receive and change addresses
+/// of an account are searched in batches of this many, until no address of a batch was used.
+const BITCOIN_SCAN_COUNT: u32 = 20;
+
+/// Number of receive and change addresses of a watch-only wallet shown in its portfolio.
+const WATCH_ONLY_PORTFOLIO_COUNT: u32 = 5;
+
+/// Confirmation target, in blocks, of the estimated Bitcoin fee rate.
+const BITCOIN_FEE_TARGET_BLOCKS: u16 = 6;
 
 pub struct Wallet {
     name: String,
-    account: Account,
+    kind: WalletKind,
+}
+
+enum WalletKind {
+    /// A wallet restored from its seed phrase, which can sign.
+    Seed {
+        language_code: String,
+        passphrase: Zeroizing<String>,
+        account: Account,
+    },
+    /// A wallet built from public keys only.
+    WatchOnly(WatchOnlyAccount),
 }
 
+/// The public view of the wallet: its name, network and addresses, never its secrets, which
+/// only [`Wallet::export_secrets`] shows.
 impl fmt::Display for Wallet {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         writeln!(
@@ -16,7 +52,10 @@ impl fmt::Display for Wallet {
             "{}{}Wallet{}: {}",
             TEXT_STYLE_BOLD, TEXT_STYLE_UNDERLINED, TEXT_STYLE_RESET, self.name
         )?;
-        writeln!(f, "{}", self.account)
+        match &self.kind {
+            WalletKind::Seed { account, .. } => writeln!(f, "{}", account),
+            WalletKind::WatchOnly(account) => writeln!(f, "Watch-only\n{}", account),
+        }
     }
 }
 
@@ -27,10 +66,15 @@ impl Wallet {
         language_code: &str,
         word_count: i32,
         passphrase: &str,
-    ) -> Result<Self, Box<dyn error::Error>> {
+        derivation: &Derivation,
+    ) -> Result<Self, WalletError> {
         Ok(Self {
             name: name.to_string(),
-            account: Account::new(net, language_code, word_count, passphrase)?,
+            kind: WalletKind::Seed {
+                language_code: language_code.to_string(),
+                passphrase: Zeroizing::new(passphrase.to_string()),
+                account: Account::new(net, language_code, word_count, passphrase, derivation)?,
+            },
         })
     }
 
@@ -40,10 +84,915 @@ impl Wallet {
         language_code: &str,
         seed_phrase: &str,
         passphrase: &str,
-    ) -> Result<Self, Box<dyn error::Error>> {
+        derivation: &Derivation,
+    ) -> Result<Self, WalletError> {
         Ok(Self {
             name: name.to_string(),
-            account: Account::restore_from_seed(net, language_code, seed_phrase, passphrase)?,
+            kind: WalletKind::Seed {
+                language_code: language_code.to_string(),
+                passphrase: Zeroizing::new(passphrase.to_string()),
+                account: Account::restore_from_seed(
+                    net,
+                    language_code,
+                    seed_phrase,
+                    passphrase,
+                    derivation,
+                )?,
+            },
         })
     }
+
+    /// Creates a watch-only wallet from a Bitcoin extended public key or output descriptor, or
+    /// a Solana public key. It holds no secrets, so it can derive addresses and query balances
+    /// but not sign.
+    pub fn watch_only(
+        name: &str,
+        net: Net,
+        key: &str,
+        bitcoin_address_type: Option<BitcoinAddressType>,
+    ) -> Result<Self, WalletError> {
+        Ok(Self {
+            name: name.to_string(),
+            kind: WalletKind::WatchOnly(WatchOnlyAccount::new(net, key, bitcoin_address_type)?),
+        })
+    }
+
+    /// Loads a wallet from the default keystore.
+    pub fn load(name: &str, password: &str) -> Result<Self, WalletError> {
+        Wallet::load_from(&Keystore::default(), name, password)
+    }
+
+    pub fn load_from(keystore: &Keystore, name: &str, password: &str) -> Result<Self, WalletError> {
+        let stored = keystore.load(name, password)?;
+        let mut wallet = Wallet::restore_from_seed(
+            &stored.name,
+            stored.net,
+            &stored.language_code,
+            &stored.secrets.seed_phrase,
+            &stored.secrets.passphrase,
+            &Derivation {
+                bitcoin: stored
+                    .bitcoin_address_type
+                    .parse()
+                    .map_err(WalletError::storage)?,
+                solana: stored
+                    .solana_derivation
+                    .parse()
+                    .map_err(WalletError::storage)?,
+            },
+        )?;
+        stored.opened.iter().try_for_each(|&(blockchain, index)| {
+            wallet.open_address(blockchain, index).map(|_| ())
+        })?;
+        Ok(wallet)
+    }
+
+    /// Lists the wallets saved in the default keystore.
+    pub fn list() -> Result<Vec<String>, WalletError> {
+        Keystore::default().list()
+    }
+
+    /// Encrypts the wallet with `password` and saves it to `keystore`.
+    pub fn save(&self, keystore: &Keystore, password: &str) -> Result<(), WalletError> {
+        let WalletKind::Seed {
+            language_code,
+            passphrase,
+            account,
+        } = &self.kind
+        else {
+            return Err(self.watch_only_error());
+        };
+        keystore.save(
+            &StoredWallet {
+                name: self.name.clone(),
+                net: account.net(),
+                language_code: language_code.clone(),
+                bitcoin_address_type: account.derivation().bitcoin.to_string(),
+                solana_derivation: account.derivation().solana.to_string(),
+                opened: account.opened(),
+                secrets: Secrets {
+                    seed_phrase: account.seed_phrase().to_string(),
+                    passphrase: passphrase.to_string(),
+                },
+            },
+            password,
+        )
+    }
+
+    /// The seed phrase, passphrase and private keys of the opened addresses, as text to back up.
+    pub fn export_secrets(&self) -> Result<Zeroizing<String>, WalletError> {
+        let WalletKind::Seed {
+            language_code,
+            passphrase,
+            account,
+        } = &self.kind
+        else {
+            return Err(self.watch_only_error());
+        };
+        Ok(write_secrets(|f| {
+            write!(f, "Wallet: {}\nLanguage: {language_code}\n", self.name)?;
+            if !passphrase.is_empty() {
+                writeln!(f, "Passphrase: {}", passphrase.as_str())?;
+            }
+            account.write_secrets(f)
+        }))
+    }
+
+    /// Writes [`Wallet::export_secrets`] to a new file at `path` that only its owner can read.
+    /// An existing file is never overwritten.
+    pub fn export_secrets_to(&self, path: &Path) -> Result<(), WalletError> {
+        let export = self.export_secrets()?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(path)?.write_all(export.as_bytes())?;
+        Ok(())
+    }
+
+    /// Saves the opened addresses of a wallet already in `keystore`.
+    pub fn save_opened(&self, keystore: &Keystore) -> Result<(), WalletError> {
+        keystore.save_opened(&self.name, &self.account()?.opened())
+    }
+
+    /// Derives the address at `index` and remembers it as opened. Watch-only wallets don't
+    /// remember addresses, they only derive them.
+    pub fn open_address(
+        &mut self,
+        blockchain: Blockchain,
+        index: AddressIndex,
+    ) -> Result<Address, WalletError> {
+        match &mut self.kind {
+            WalletKind::Seed { account, .. } => account.open(blockchain, index),
+            WalletKind::WatchOnly(account) => account.derive_address(blockchain, index),
+        }
+    }
+
+    /// Derives the address at `index` without remembering it.
+    pub fn derive_address(
+        &self,
+        blockchain: Blockchain,
+        index: AddressIndex,
+    ) -> Result<Address, WalletError> {
+        match &self.kind {
+            WalletKind::Seed { account, .. } => account.derive_address(blockchain, index),
+            WalletKind::WatchOnly(account) => account.derive_address(blockchain, index),
+        }
+    }
+
+    /// The addresses of the wallet: the opened ones, or the first ones of account 0 of a
+    /// watch-only wallet. They are ordered by blockchain and index. [`Wallet::balances`] also
+    /// finds the Bitcoin addresses that hold funds without being opened.
+    pub fn addresses(&self) -> Result<Vec<(Blockchain, AddressIndex, Address)>, WalletError> {
+        match &self.kind {
+            WalletKind::Seed { account, .. } => Ok(account
+                .opened()
+                .into_iter()
+                .filter_map(|(blockchain, index)| {
+                    let address = account.address(blockchain, index)?;
+                    Some((blockchain, index, address.clone()))
+                })
+                .collect()),
+            WalletKind::WatchOnly(account) => {
+                let blockchain = account.blockchain();
+                let mut addresses =
+                    account.list_addresses(blockchain, 0, WATCH_ONLY_PORTFOLIO_COUNT)?;
+                addresses.sort();
+                Ok(addresses
+                    .into_iter()
+                    .map(|(index, address)| (blockchain, index, address))
+                    .collect())
+            }
+        }
+    }
+
+    /// Queries the native balances of all the addresses of the wallet at the same time, and of
+    /// the Bitcoin accounts that have addresses, which are scanned up to their gap limit. An
+    /// address whose balance can't be queried keeps the error, the others are still returned.
+    pub async fn balances(&self, api: &Api) -> Result<Portfolio, WalletError> {
+        let api = self.check_net(api)?;
+        let addresses = self.addresses()?;
+        let mut accounts = addresses
+            .iter()
+            .filter(|(blockchain, _, _)| *blockchain == Blockchain::Bitcoin)
+            .map(|(_, index, _)| index.account)
+            .collect::<Vec<_>>();
+        accounts.dedup();
+        let mut scanned = vec![];
+        for account in accounts {
+            let addresses = self.scan_bitcoin(api, account).await?;
+            scanned.extend(addresses.into_iter().map(|scanned| scanned.balance));
+        }
+        let is_scanned = |blockchain: Blockchain, index: AddressIndex| {
+            scanned
+                .iter()
+                .any(|scanned| scanned.blockchain == blockchain && scanned.index == index)
+        };
+
+        let (known, unscanned) = addresses
+            .into_iter()
+            .partition::<Vec<_>, _>(|&(blockchain, index, _)| is_scanned(blockchain, index));
+        let queries = unscanned
+            .iter()
+            .map(|(blockchain, _, address)| (*blockchain, address.clone()))
+            .collect::<Vec<_>>();
+        let balances = api.get_balances(&queries).await;
+        let mut addresses = unscanned
+            .into_iter()
+            .zip(balances)
+            .map(|((blockchain, index, address), balance)| AddressBalance {
+                blockchain,
+                index,
+                address,
+                balance,
+            })
+            .chain(scanned.into_iter().filter(|scanned| {
+                may_hold_funds(scanned)
+                    || known.iter().any(|&(blockchain, index, _)| {
+                        scanned.blockchain == blockchain && scanned.index == index
+                    })
+            }))
+            .collect::<Vec<_>>();
+        addresses.sort_by_key(|address| (address.blockchain, address.index));
+        Ok(Portfolio { addresses })
+    }
+
+    /// Queries the balances of the receive and change addresses of a Bitcoin account, a batch
+    /// of [`BITCOIN_SCAN_COUNT`] at a time, until no address of a batch was ever used. An
+    /// address spent to zero still counts as used, since later ones may hold funds. Returns
+    /// all the scanned addresses.
+    async fn scan_bitcoin(
+        &self,
+        api: &Api,
+        account: u32,
+    ) -> Result<Vec<ScannedAddress>, WalletError> {
+        let mut scanned = vec![];
+        for change in [false, true] {
+            for batch in 0.. {
+                let start = batch * BITCOIN_SCAN_COUNT;
+                let indexes = (start..start + BITCOIN_SCAN_COUNT)
+                    .map(|i| AddressIndex::new(account, change, i))
+                    .collect::<Vec<_>>();
+                let addresses = indexes
+                    .iter()
+                    .map(|&index| self.derive_address(Blockchain::Bitcoin, index))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let activities = api
+                    .get_address_activities(Blockchain::Bitcoin, &addresses)
+                    .await;
+                let used = activities
+                    .iter()
+                    .any(|activity| activity.as_ref().is_ok_and(|activity| activity.used));
+                scanned.extend(indexes.into_iter().zip(addresses).zip(activities).map(
+                    |((index, address), activity)| ScannedAddress {
+                        used: activity.as_ref().map_or(true, |activity| activity.used),
+                        balance: AddressBalance {
+                            blockchain: Blockchain::Bitcoin,
+                            index,
+                            address,
+                            balance: activity.map(|activity| activity.balance),
+                        },
+                    },
+                ));
+                if !used {
+                    break;
+                }
+            }
+        }
+        Ok(scanned)
+    }
+
+    /// Lists the first `count` receive and change addresses of an account.
+    pub fn list_addresses(
+        &self,
+        blockchain: Blockchain,
+        account: u32,
+        count: u32,
+    ) -> Result<Vec<(AddressIndex, Address)>, WalletError> {
+        match &self.kind {
+            WalletKind::Seed { account: a, .. } => a.list_addresses(blockchain, account, count),
+            WalletKind::WatchOnly(a) => a.list_addresses(blockchain, account, count),
+        }
+    }
+
+    /// Queries the balance of the address at `index`.
+    pub async fn balance(
+        &self,
+        api: &Api,
+        blockchain: Blockchain,
+        index: AddressIndex,
+    ) -> Result<Amount, WalletError> {
+        let address = self.derive_address(blockchain, index)?;
+        self.check_net(api)?.get_balance(blockchain, &address).await
+    }
+
+    /// Sends `amount` of the native coin or of a token from the address at `index` of its
+    /// blockchain to the owner `to`, and returns the transaction signature once it reaches
+    /// `commitment`.
+    pub async fn transfer(
+        &self,
+        api: &Api,
+        index: AddressIndex,
+        to: &Address,
+        amount: &Amount,
+        commitment: Commitment,
+    ) -> Result<String, WalletError> {
+        let secret_key = self
+            .account()?
+            .secret_key(amount.asset.blockchain(), index)?;
+        self.check_net(api)?
+            .transfer(&secret_key, to, amount, commitment)
+            .await
+    }
+
+    /// The token balances of the address at `index`: all its SPL tokens on Solana, the
+    /// listed `tokens` on Ethereum.
+    pub async fn token_balances(
+        &self,
+        api: &Api,
+        blockchain: Blockchain,
+        index: AddressIndex,
+        tokens: &[Address],
+    ) -> Result<Vec<TokenBalance>, WalletError> {
+        let address = self.derive_address(blockchain, index)?;
+        self.check_net(api)?
+            .get_token_balances(blockchain, &address, tokens)
+            .await
+    }
+
+    /// The transactions of the address at `index`, newest first: at most `limit` of them,
+    /// older than the transaction `before` if given.
+    pub async fn transactions(
+        &self,
+        api: &Api,
+        blockchain: Blockchain,
+        index: AddressIndex,
+        limit: usize,
+        before: Option<&str>,
+    ) -> Result<Vec<TransactionSummary>, WalletError> {
+        let address = self.derive_address(blockchain, index)?;
+        self.check_net(api)?
+            .get_transactions(blockchain, &address, limit, before)
+            .await
+    }
+
+    /// Sends `amount` satoshis to `to` from the confirmed UTXOs of a Bitcoin account, and
+    /// returns the id of the broadcast transaction. The account is scanned for funds up to its
+    /// gap limit, as by [`Wallet::balances`].
+    ///
+    /// The fee rate, in sat/vB, is estimated if not given. Change goes to the change address
+    /// after the last opened or used one, which is opened.
+    pub async fn send_bitcoin(
+        &mut self,
+        api: &Api,
+        account: u32,
+        to: &Address,
+        amount: u64,
+        fee_rate: Option<f64>,
+    ) -> Result<String, WalletError> {
+        let api = self.check_net(api)?;
+        let keys = self.account()?;
+        let opened = keys
+            .opened()
+            .into_iter()
+            .filter(|&(blockchain, index)| {
+                blockchain == Blockchain::Bitcoin && index.account == account
+            })
+            .map(|(_, index)| index)
+            .collect::<Vec<_>>();
+        let scanned = self.scan_bitcoin(api, account).await?;
+        let change = opened
+            .iter()
+            .copied()
+            .chain(
+                scanned
+                    .iter()
+                    .filter(|scanned| scanned.used)
+                    .map(|scanned| scanned.balance.index),
+            )
+            .filter(|index| index.change)
+            .map(|index| index.index + 1)
+            .max()
+            .map_or(AddressIndex::new(account, true, 0), |i| {
+                AddressIndex::new(account, true, i)
+            });
+
+        let mut indexes = scanned
+            .into_iter()
+            .filter(|scanned| may_hold_funds(&scanned.balance))
+            .map(|scanned| scanned.balance.index)
+            .chain(opened)
+            .collect::<Vec<_>>();
+        indexes.sort();
+        indexes.dedup();
+        let addresses = indexes
+            .iter()
+            .map(|&index| keys.derive_address(Blockchain::Bitcoin, index))
+            .collect::<Result<Vec<_>, _>>()?;
+        let utxos = try_join_all(
+            addresses
+                .iter()
+                .map(|address| api.get_utxos(Blockchain::Bitcoin, address)),
+        )
+        .await?
+        .into_iter()
+        .zip(indexes)
+        .flat_map(|(utxos, index)| {
+            utxos
+                .into_iter()
+                .filter(|utxo| utxo.confirmed)
+                .map(move |utxo| OwnedUtxo { utxo, index })
+        })
+        .collect::<Vec<_>>();
+
+        let fee_rate = match fee_rate {
+            Some(fee_rate) if !(fee_rate.is_finite() && fee_rate > 0.0) => {
+                return Err(WalletError::InvalidAmount(format!(
+                    "Invalid fee rate {fee_rate} sat/vB"
+                )))
+            }
+            Some(fee_rate) => fee_rate,
+            None => {
+                api.get_fee_rate(Blockchain::Bitcoin, BITCOIN_FEE_TARGET_BLOCKS)
+                    .await?
+            }
+        };
+        let mut psbt = build_psbt(keys, &utxos, to, amount, fee_rate, change)?;
+        keys.sign_bitcoin_psbt(&mut psbt)?;
+        let has_change = psbt.unsigned_tx.output.len() > 1;
+        let transaction = psbt.extract_tx().map_err(WalletError::crypto)?;
+        let txid = api
+            .broadcast(
+                Blockchain::Bitcoin,
+                &bitcoin::consensus::serialize(&transaction),
+            )
+            .await?;
+        if has_change {
+            self.open_address(Blockchain::Bitcoin, change)?;
+        }
+        Ok(txid)
+    }
+
+    /// Requests `amount` of test coins for the address at `index` of its blockchain.
+    pub async fn request_airdrop(
+        &self,
+        api: &Api,
+        index: AddressIndex,
+        amount: &Amount,
+    ) -> Result<String, WalletError> {
+        let address = self.derive_address(amount.asset.blockchain(), index)?;
+        self.check_net(api)?.request_airdrop(&address, amount).await
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn net(&self) -> Net {
+        match &self.kind {
+            WalletKind::Seed { account, .. } => account.net(),
+            WalletKind::WatchOnly(account) => account.net(),
+        }
+    }
+
+    pub fn is_watch_only(&self) -> bool {
+        matches!(self.kind, WalletKind::WatchOnly(_))
+    }
+
+    /// The account holding the keys of the wallet, needed to sign. Fails with
+    /// [`WalletError::WatchOnly`] on a watch-only wallet.
+    pub fn account(&self) -> Result<&Account, WalletError> {
+        match &self.kind {
+            WalletKind::Seed { account, .. } => Ok(account),
+            WalletKind::WatchOnly(_) => Err(self.watch_only_error()),
+        }
+    }
+
+    /// The public keys of a watch-only wallet.
+    pub fn watch_only_account(&self) -> Option<&WatchOnlyAccount> {
+        match &self.kind {
+            WalletKind::Seed { .. } => None,
+            WalletKind::WatchOnly(account) => Some(account),
+        }
+    }
+
+    /// Fails if `api` is on another network than the wallet, whose addresses and keys wouldn't
+    /// be valid there.
+    fn check_net<'a>(&self, api: &'a Api) -> Result<&'a Api, WalletError> {
+        if api.net() != self.net() {
+            return Err(WalletError::WrongNetwork(format!(
+                "Wallet {} is on {}, not on {}",
+                self.name,
+                self.net(),
+                api.net()
+            )));
+        }
+        Ok(api)
+    }
+
+    fn watch_only_error(&self) -> WalletError {
+        WalletError::WatchOnly {
+            wallet: self.name.clone(),
+        }
+    }
+}
+
+/// A Bitcoin address found by [`Wallet::scan_bitcoin`].
+struct ScannedAddress {
+    balance: AddressBalance,
+    /// Whether the address was ever paid, or its activity couldn't be queried.
+    used: bool,
+}
+
+/// Whether an address has a balance, or one that couldn't be queried.
+fn may_hold_funds(address: &AddressBalance) -> bool {
+    address
+        .balance
+        .as_ref()
+        .map_or(true, |balance| !balance.units.is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use api::endpoint::{ApiConfig, Endpoint};
+    use keypair::SolanaDerivation;
+
+    const SEED_PHRASE: &str =
+        "brisk fossil brisk dream dry sing lottery mountain sound void refuse pumpkin";
+
+    fn restored(net: Net) -> Wallet {
+        Wallet::restore_from_seed("main", net, "en", SEED_PHRASE, "", &Derivation::default())
+            .unwrap()
+    }
+
+    /// The Esplora statistics of an address paid `funded` satoshis in `count` outputs, of which
+    /// `spent` were spent.
+    fn address_stats(count: u64, funded: u64, spent: u64) -> String {
+        format!(
+            r#"{{"chain_stats": {{"funded_txo_count": {count}, "funded_txo_sum": {funded}, "spent_txo_sum": {spent}}}}}"#
+        )
+    }
+
+    fn esplora_api(server: &mockito::Server) -> Api {
+        let mut config = ApiConfig::default();
+        config
+            .endpoints
+            .insert(Blockchain::Bitcoin, Endpoint::new(server.url()));
+        Api::new(Net::Local, &config).unwrap()
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = Keystore::new(dir.path()).with_scrypt_log_n(4);
+        let wallet = Wallet::restore_from_seed(
+            "main",
+            Net::Test,
+            "en",
+            SEED_PHRASE,
+            "paSword!",
+            &Derivation {
+                bitcoin: BitcoinAddressType::P2tr,
+                solana: SolanaDerivation::Legacy,
+            },
+        )
+        .unwrap();
+        wallet.save(&keystore, "secret").unwrap();
+
+        let mut loaded = Wallet::load_from(&keystore, "main", "secret").unwrap();
+        let index = AddressIndex::new(1, false, 5);
+        let address = loaded.open_address(Blockchain::Ethereum, index).unwrap();
+        loaded.save_opened(&keystore).unwrap();
+
+        let loaded = Wallet::load_from(&keystore, "main", "secret").unwrap();
+        assert_eq!(loaded.name(), "main");
+        assert_eq!(loaded.net(), Net::Test);
+        let (account, expected) = (loaded.account().unwrap(), wallet.account().unwrap());
+        assert_eq!(account.seed_phrase(), expected.seed_phrase());
+        assert!(
+            matches!(&loaded.kind, WalletKind::Seed { passphrase, .. } if passphrase.as_str() == "paSword!")
+        );
+        assert_eq!(account.derivation(), expected.derivation());
+        assert_eq!(account.address(Blockchain::Ethereum, index), Some(&address));
+    }
+
+    #[tokio::test]
+    async fn watch_only() {
+        let wallet = restored(Net::Test);
+        let xpub = wallet.account().unwrap().bitcoin_xpub(0).unwrap();
+        let watch = Wallet::watch_only("watch", Net::Test, &xpub.descriptor(false), None).unwrap();
+        assert!(watch.is_watch_only());
+        assert_eq!(
+            watch.list_addresses(Blockchain::Bitcoin, 0, 2).unwrap(),
+            wallet.list_addresses(Blockchain::Bitcoin, 0, 2).unwrap()
+        );
+        assert_eq!(
+            watch.account().err(),
+            Some(WalletError::WatchOnly {
+                wallet: "watch".to_string()
+            })
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let error = watch
+            .save(&Keystore::new(dir.path()).with_scrypt_log_n(4), "secret")
+            .unwrap_err();
+        assert!(matches!(error, WalletError::WatchOnly { .. }));
+
+        let error = watch
+            .transfer(
+                &Api::new(Net::Test, &ApiConfig::default()).unwrap(),
+                AddressIndex::default(),
+                &wallet
+                    .derive_address(Blockchain::Bitcoin, AddressIndex::new(0, false, 1))
+                    .unwrap(),
+                &Amount::native(Blockchain::Bitcoin, 1000),
+                Commitment::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WalletError::WatchOnly { .. }));
+    }
+
+    #[test]
+    fn export_secrets() {
+        let wallet = Wallet::restore_from_seed(
+            "main",
+            Net::Test,
+            "en",
+            SEED_PHRASE,
+            "paSword!",
+            &Derivation::default(),
+        )
+        .unwrap();
+        let public = wallet.to_string();
+        assert!(!public.contains("brisk"));
+        assert!(!public.contains("paSword!"));
+
+        let secrets = wallet.export_secrets().unwrap();
+        assert_eq!(secrets.capacity(), secrets.len());
+        assert!(secrets.starts_with("Wallet: main\nLanguage: en\nPassphrase: paSword!\n"));
+        assert!(secrets.contains("brisk fossil brisk dream"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("main-secrets.txt");
+        wallet.export_secrets_to(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), *secrets);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        // An existing file is kept.
+        assert!(matches!(
+            wallet.export_secrets_to(&path),
+            Err(WalletError::Storage(_))
+        ));
+
+        let xpub = wallet.account().unwrap().bitcoin_xpub(0).unwrap();
+        let watch = Wallet::watch_only("watch", Net::Test, &xpub.descriptor(false), None).unwrap();
+        assert!(matches!(
+            watch.export_secrets(),
+            Err(WalletError::WatchOnly { .. })
+        ));
+    }
+
+    #[test]
+    fn addresses() {
+        let mut wallet = restored(Net::Test);
+        let index = AddressIndex::new(0, false, 1);
+        let address = wallet.open_address(Blockchain::Solana, index).unwrap();
+        let addresses = wallet.addresses().unwrap();
+        assert_eq!(
+            addresses
+                .iter()
+                .map(|(blockchain, index, _)| (*blockchain, *index))
+                .collect::<Vec<_>>(),
+            vec![
+                (Blockchain::Bitcoin, AddressIndex::default()),
+                (Blockchain::Ethereum, AddressIndex::default()),
+                (Blockchain::Solana, AddressIndex::default()),
+                (Blockchain::Solana, index),
+            ]
+        );
+        assert_eq!(addresses[3].2, address);
+
+        let xpub = wallet.account().unwrap().bitcoin_xpub(0).unwrap();
+        let watch = Wallet::watch_only("watch", Net::Test, &xpub.descriptor(false), None).unwrap();
+        let addresses = watch.addresses().unwrap();
+        assert_eq!(addresses.len(), 2 * WATCH_ONLY_PORTFOLIO_COUNT as usize);
+        assert_eq!(
+            addresses[0].2,
+            wallet
+                .derive_address(Blockchain::Bitcoin, AddressIndex::default())
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn balances_scan_bitcoin() {
+        let mut wallet = restored(Net::Local);
+        let opened = AddressIndex::new(0, false, 1);
+        wallet.open_address(Blockchain::Bitcoin, opened).unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let funded = [
+            (AddressIndex::new(0, false, 5), 1000),
+            (AddressIndex::new(0, false, BITCOIN_SCAN_COUNT + 5), 2000),
+            (AddressIndex::new(0, true, 3), 500),
+        ];
+        for (index, sats) in funded {
+            let address = wallet.derive_address(Blockchain::Bitcoin, index).unwrap();
+            server
+                .mock("GET", format!("/address/{address}").as_str())
+                .with_body(address_stats(1, sats, 0))
+                .create_async()
+                .await;
+        }
+        // Three batches of receive addresses and two of change addresses, the last ones unused.
+        let unused = server
+            .mock("GET", mockito::Matcher::Regex("^/address/".to_string()))
+            .with_body(address_stats(0, 0, 0))
+            .expect(5 * BITCOIN_SCAN_COUNT as usize - funded.len())
+            .create_async()
+            .await;
+
+        let portfolio = wallet.balances(&esplora_api(&server)).await.unwrap();
+        assert_eq!(
+            portfolio
+                .addresses(Blockchain::Bitcoin)
+                .map(|address| address.index)
+                .collect::<Vec<_>>(),
+            [
+                AddressIndex::default(),
+                opened,
+                funded[0].0,
+                funded[1].0,
+                funded[2].0
+            ]
+        );
+        assert_eq!(
+            portfolio.total(Blockchain::Bitcoin).unwrap(),
+            Amount::native(Blockchain::Bitcoin, 3500)
+        );
+        unused.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn balances_scan_past_spent_addresses() {
+        let wallet = restored(Net::Local);
+        let mut server = mockito::Server::new_async().await;
+        // A whole batch of addresses that were paid, then spent to zero.
+        for index in 0..BITCOIN_SCAN_COUNT {
+            let index = AddressIndex::new(0, false, index);
+            let address = wallet.derive_address(Blockchain::Bitcoin, index).unwrap();
+            server
+                .mock("GET", format!("/address/{address}").as_str())
+                .with_body(address_stats(1, 1000, 1000))
+                .create_async()
+                .await;
+        }
+        let funded = AddressIndex::new(0, false, BITCOIN_SCAN_COUNT + 3);
+        let address = wallet.derive_address(Blockchain::Bitcoin, funded).unwrap();
+        server
+            .mock("GET", format!("/address/{address}").as_str())
+            .with_body(address_stats(2, 7000, 0))
+            .create_async()
+            .await;
+        server
+            .mock("GET", mockito::Matcher::Regex("^/address/".to_string()))
+            .with_body(address_stats(0, 0, 0))
+            .create_async()
+            .await;
+
+        let portfolio = wallet.balances(&esplora_api(&server)).await.unwrap();
+        assert_eq!(
+            portfolio
+                .addresses(Blockchain::Bitcoin)
+                .map(|address| address.index)
+                .collect::<Vec<_>>(),
+            [AddressIndex::default(), funded]
+        );
+        assert_eq!(
+            portfolio.total(Blockchain::Bitcoin).unwrap(),
+            Amount::native(Blockchain::Bitcoin, 7000)
+        );
+    }
+
+    #[tokio::test]
+    async fn send_bitcoin_skips_used_change() {
+        let mut wallet = restored(Net::Local);
+        let funded = wallet
+            .derive_address(Blockchain::Bitcoin, AddressIndex::default())
+            .unwrap();
+        let used_change = AddressIndex::new(0, true, 0);
+        let change = AddressIndex::new(0, true, 1);
+        let address = |index| wallet.derive_address(Blockchain::Bitcoin, index).unwrap();
+        let script = |address: &str| {
+            let address = address.parse::<bitcoin::Address<_>>().unwrap();
+            hex::encode(address.assume_checked().script_pubkey().as_bytes())
+        };
+        let change_script = script(&address(change));
+
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", format!("/address/{funded}").as_str())
+            .with_body(address_stats(1, 100_000, 0))
+            .create_async()
+            .await;
+        server
+            .mock("GET", format!("/address/{funded}/utxo").as_str())
+            .with_body(
+                r#"[{
+                    "txid": "3a2b1f7c5e3b1f0a1c5d6e7f8a9b0c1d2e3f4a5b6c7d8e9f0a1b2c3d4e5f6a7b",
+                    "vout": 0,
+                    "status": {"confirmed": true, "block_height": 101},
+                    "value": 100000
+                }]"#,
+            )
+            .create_async()
+            .await;
+        // The first change address was paid and spent, e.g. by another copy of the wallet.
+        server
+            .mock("GET", format!("/address/{}", address(used_change)).as_str())
+            .with_body(address_stats(1, 5000, 5000))
+            .create_async()
+            .await;
+        server
+            .mock(
+                "GET",
+                mockito::Matcher::Regex("^/address/[^/]+$".to_string()),
+            )
+            .with_body(address_stats(0, 0, 0))
+            .create_async()
+            .await;
+        let broadcast = server
+            .mock("POST", "/tx")
+            .match_body(mockito::Matcher::Regex(change_script))
+            .with_body("b6f6991d03df0e2e04dafffcd6bc418aac66049e2cd74b80f14ac86db1e3f0da")
+            .create_async()
+            .await;
+
+        let to = address(AddressIndex::new(1, false, 0));
+        wallet
+            .send_bitcoin(&esplora_api(&server), 0, &to, 10_000, Some(2.0))
+            .await
+            .unwrap();
+        broadcast.assert_async().await;
+        let account = wallet.account().unwrap();
+        assert!(account.address(Blockchain::Bitcoin, change).is_some());
+        assert!(account.address(Blockchain::Bitcoin, used_change).is_none());
+    }
+
+    #[tokio::test]
+    async fn wrong_network() {
+        let wallet = restored(Net::Test);
+        let error = wallet
+            .balance(
+                &Api::new(Net::Main, &ApiConfig::default()).unwrap(),
+                Blockchain::Solana,
+                AddressIndex::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, WalletError::WrongNetwork(_)));
+    }
+
+    /// Needs a regtest `bitcoind` with `electrs` serving the Esplora API on localhost:3002,
+    /// and a confirmed UTXO on the first address of the wallet, e.g.
+    /// `bitcoin-cli -regtest generatetoaddress 101 bcrt1q6rz28mcfaxtmd6v789l9rrlrusdprr9pz3cppk`.
+    #[tokio::test]
+    #[ignore]
+    async fn send_bitcoin_on_regtest() {
+        let mut wallet = Wallet::restore_from_seed(
+            "regtest",
+            Net::Local,
+            "en",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "",
+            &Derivation::default(),
+        )
+        .unwrap();
+        let to = wallet
+            .derive_address(Blockchain::Bitcoin, AddressIndex::new(1, false, 0))
+            .unwrap();
+        let txid = wallet
+            .send_bitcoin(
+                &Api::new(Net::Local, &ApiConfig::default()).unwrap(),
+                0,
+                &to,
+                100_000,
+                Some(2.0),
+            )
+            .await
+            .unwrap();
+        assert_eq!(txid.len(), 64);
+        assert!(wallet
+            .account()
+            .unwrap()
+            .address(Blockchain::Bitcoin, AddressIndex::new(0, true, 0))
+            .is_some());
+    }
 }